@@ -0,0 +1,90 @@
+//! Measures the throughput of the parser on pipelined HAP traffic.
+//!
+//! Run with `cargo bench`.
+
+extern crate hap_http_parser;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback};
+
+const MESSAGES: usize = 1000;
+
+struct Callback;
+
+impl HttpParserCallback for Callback {}
+
+// Requests of a controller to an accessory
+fn requests() -> String {
+    let body = "{\"characteristics\":[{\"aid\":1,\"iid\":10,\"value\":true},\
+                {\"aid\":1,\"iid\":11,\"value\":75}]}";
+    let tlv = "\x06\x01\x03\x03\x20abcdefghijklmnopqrstuvwxyz012345";
+    let mut data = String::new();
+    for i in 0..MESSAGES {
+        match i % 3 {
+            0 => data.push_str("GET /characteristics?id=1.10,1.11&meta=1&perms=1 HTTP/1.1\r\n\
+                                Host: lights.local:51826\r\n\
+                                \r\n"),
+            1 => data.push_str(&format!("PUT /characteristics HTTP/1.1\r\n\
+                                         Host: lights.local:51826\r\n\
+                                         Content-Type: application/hap+json\r\n\
+                                         Content-Length: {}\r\n\
+                                         \r\n\
+                                         {}", body.len(), body)),
+            _ => data.push_str(&format!("POST /pair-verify HTTP/1.1\r\n\
+                                         Host: lights.local:51826\r\n\
+                                         Content-Type: application/pairing+tlv8\r\n\
+                                         Content-Length: {}\r\n\
+                                         \r\n\
+                                         {}", tlv.len(), tlv)),
+        }
+    }
+    data
+}
+
+// Responses and notifications of an accessory to a controller
+fn responses() -> String {
+    let body = "{\"characteristics\":[{\"aid\":1,\"iid\":10,\"value\":false}]}";
+    let mut data = String::new();
+    for i in 0..MESSAGES {
+        if i % 2 == 0 {
+            data.push_str("HTTP/1.1 204 No Content\r\n\r\n");
+        } else {
+            data.push_str(&format!("EVENT/1.0 200 OK\r\n\
+                                    Content-Type: application/hap+json\r\n\
+                                    Content-Length: {}\r\n\
+                                    \r\n\
+                                    {}", body.len(), body));
+        }
+    }
+    data
+}
+
+fn bench(name: &str, tp: HttpParserType, data: &[u8]) {
+    // Warm up
+    parse(tp, data);
+
+    let mut iterations = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(2) {
+        parse(tp, data);
+        iterations += 1;
+    }
+    let seconds = start.elapsed().as_secs_f64();
+
+    println!("{:<10} {:>10.1} MB/s {:>14.0} messages/s", name,
+             (data.len() * iterations) as f64 / seconds / 1e6,
+             (MESSAGES * iterations) as f64 / seconds);
+}
+
+fn parse(tp: HttpParserType, data: &[u8]) {
+    let mut hp = HttpParser::new(tp);
+    let parsed = hp.execute(&mut Callback, black_box(data));
+    assert_eq!(parsed, data.len());
+}
+
+fn main() {
+    bench("requests", HttpParserType::Request, requests().as_bytes());
+    bench("responses", HttpParserType::Response, responses().as_bytes());
+}
@@ -8,6 +8,9 @@ pub enum ParseAction {
     None,
     /// Skip body
     SkipBody,
+    /// Pause the parser right after the current event. `execute` returns the number of bytes
+    /// consumed so far and parsing continues from the next byte after `resume`.
+    Pause,
 }
 
 /// Result of a callback function.
@@ -70,12 +73,75 @@ pub trait HttpParserCallback {
         Ok(ParseAction::None)
     }
 
+    /// Function called after `on_headers_complete` for a request with a body and an
+    /// `Expect: 100-continue` header, before any of the body is parsed.
+    ///
+    /// Returning `ParseAction::None` accepts the body, so the server should send
+    /// `100 Continue`. `ParseAction::SkipBody` rejects it: the message is complete without a
+    /// body and, since the client may send the body anyway, the connection isn't kept alive.
+    /// `ParseAction::Pause` pauses the parser until the server decides, then
+    /// `HttpParser::reject_expect` before `HttpParser::resume` rejects it.
+    #[allow(unused_variables)]
+    fn on_expect_continue(&mut self, parser: &mut HttpParser) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
     /// Function called when the body is parsed.
     #[allow(unused_variables)]
     fn on_body(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
         Ok(ParseAction::None)
     }
 
+    /// Function called when the size line of a chunk is parsed, before any of its data.
+    ///
+    /// It is also called with a `size` of 0 for the last chunk.
+    #[allow(unused_variables)]
+    fn on_chunk_header(&mut self, parser: &mut HttpParser, size: u64) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
+    /// Function called when the name of a chunk extension is parsed.
+    #[allow(unused_variables)]
+    fn on_chunk_extension_name(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
+    /// Function called when the value of a chunk extension is parsed.
+    ///
+    /// A quoted value is passed as is, including the quotes and any escapes.
+    #[allow(unused_variables)]
+    fn on_chunk_extension_value(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
+    /// Function called when the data of a chunk is parsed.
+    ///
+    /// For the last chunk it is called after the trailers.
+    #[allow(unused_variables)]
+    fn on_chunk_complete(&mut self, parser: &mut HttpParser) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
+    /// Function called when a trailer field of a chunked message is parsed.
+    ///
+    /// By default it is passed on to `on_header_field`.
+    fn on_trailer_field(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+        self.on_header_field(parser, data)
+    }
+
+    /// Function called when a trailer value of a chunked message is parsed.
+    ///
+    /// By default it is passed on to `on_header_value`.
+    fn on_trailer_value(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+        self.on_header_value(parser, data)
+    }
+
+    /// Function called when all trailers of a chunked message are parsed.
+    #[allow(unused_variables)]
+    fn on_trailers_complete(&mut self, parser: &mut HttpParser) -> CallbackResult {
+        Ok(ParseAction::None)
+    }
+
     /// Function called when finishing parsing a HTTP request or response.
     #[allow(unused_variables)]
     fn on_message_complete(&mut self, parser: &mut HttpParser) -> CallbackResult {
@@ -0,0 +1,125 @@
+//! Decoding of the percent-encoded components of a URL.
+
+use std::borrow::Cow;
+
+use error::HttpErrno;
+use parser::unhex_value;
+
+// Returns the byte of the percent-escape at `index`, if there is one
+fn escaped_byte(data: &[u8], index: usize) -> Option<u8> {
+    if data.get(index) != Some(&b'%') {
+        return Option::None;
+    }
+
+    let hi = data.get(index + 1).and_then(|&ch| unhex_value(ch))?;
+    let lo = data.get(index + 2).and_then(|&ch| unhex_value(ch))?;
+    Option::Some((hi * 16 + lo) as u8)
+}
+
+// Decodes the percent-escapes of `data`, and '+' as a space if `plus_as_space` is set. A '%'
+// that doesn't start an escape is kept as is
+fn percent_decode<'a>(data: &'a [u8], plus_as_space: bool) -> Cow<'a, [u8]> {
+    if !data.iter().any(|&ch| ch == b'%' || (plus_as_space && ch == b'+')) {
+        return Cow::Borrowed(data);
+    }
+
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut index = 0;
+    while index < data.len() {
+        match (data[index], escaped_byte(data, index)) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            },
+            (b'+', None) if plus_as_space => decoded.push(b' '),
+            (ch, None) => decoded.push(ch),
+        }
+        index += 1;
+    }
+    Cow::Owned(decoded)
+}
+
+/// Decodes the percent-escapes of a URL path.
+///
+/// Fails with `HttpErrno::InvalidPath` if a '%' isn't followed by two hex digits, if the path
+/// has an encoded NUL byte, or if a segment of the decoded path is `..`, which could escape the
+/// directory the path is resolved in. `+` isn't decoded as a space in a path.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// assert_eq!(&*percent_decode_path(b"/my%20light").unwrap(), b"/my light");
+/// assert_eq!(percent_decode_path(b"/a/%2e%2E/b"), Err(HttpErrno::InvalidPath));
+/// ```
+pub fn percent_decode_path<'a>(path: &'a [u8]) -> Result<Cow<'a, [u8]>, HttpErrno> {
+    for (index, _) in path.iter().enumerate().filter(|&(_, &ch)| ch == b'%') {
+        match escaped_byte(path, index) {
+            None | Some(0) => return Err(HttpErrno::InvalidPath),
+            Some(_) => (),
+        }
+    }
+
+    let decoded = percent_decode(path, false);
+    if decoded.split(|&ch| ch == b'/').any(|segment| segment == b"..") {
+        return Err(HttpErrno::InvalidPath);
+    }
+    Ok(decoded)
+}
+
+/// `Query` iterates over the decoded `(key, value)` pairs of a query string, as in an
+/// `application/x-www-form-urlencoded` form.
+///
+/// Pairs are separated by `&`, and the key is separated from the value by the first `=`. A pair
+/// without `=` has an empty value, and empty pairs are skipped. `+` is decoded as a space, and
+/// a `%` that isn't followed by two hex digits is kept as is. A repeated key is yielded once per
+/// pair, in order. The pairs are borrowed from the query unless they have to be decoded.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let url = b"/characteristics?id=1.4,1.5&meta=1&ev=1";
+/// let fields = parse_url(url, false).unwrap();
+/// let mut query = Query::new(fields.query.unwrap().slice(url));
+///
+/// let (key, value) = query.next().unwrap();
+/// assert_eq!((&*key, &*value), (&b"id"[..], &b"1.4,1.5"[..]));
+/// assert_eq!(query.count(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Query<'a> {
+    query: &'a [u8], // pairs that are still to be yielded
+}
+
+impl<'a> Query<'a> {
+    /// Creates an iterator over the pairs of `query`, the part of the URL after `?`.
+    pub fn new(query: &'a [u8]) -> Query<'a> {
+        Query { query }
+    }
+}
+
+impl<'a> Iterator for Query<'a> {
+    type Item = (Cow<'a, [u8]>, Cow<'a, [u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.query.is_empty() {
+            let end = self.query.iter().position(|&ch| ch == b'&').unwrap_or(self.query.len());
+            let pair = &self.query[.. end];
+            self.query = self.query.get(end + 1 ..).unwrap_or(&[]);
+
+            if pair.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match pair.iter().position(|&ch| ch == b'=') {
+                Some(eq) => (&pair[.. eq], &pair[eq + 1 ..]),
+                None => (pair, &pair[pair.len() ..]),
+            };
+            return Option::Some((percent_decode(key, true), percent_decode(value, true)));
+        }
+
+        Option::None
+    }
+}
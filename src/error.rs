@@ -1,7 +1,12 @@
+use std::error;
 use std::fmt;
 
 /// `HttpErrno` defines the encountered error during parsing.
-#[derive(PartialEq, Eq, Copy, Clone)]
+///
+/// Every errno has a name and a numeric code that don't change between releases. The errnos
+/// that exist in joyent's http-parser keep its names and codes. The callback errnos added by
+/// this crate are numbered from 64 and the parsing errnos from 96.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum HttpErrno {
     // Callback-related errors
     /// Error happened in message begin callback
@@ -20,12 +25,36 @@ pub enum HttpErrno {
     CBMessageComplete,
     /// Error happened in status callback
     CBStatus,
+    /// Error happened in trailer field callback
+    CBTrailerField,
+    /// Error happened in trailer value callback
+    CBTrailerValue,
+    /// Error happened in trailers complete callback
+    CBTrailersComplete,
+    /// Error happened in chunk header callback
+    CBChunkHeader,
+    /// Error happened in chunk extension name callback
+    CBChunkExtensionName,
+    /// Error happened in chunk extension value callback
+    CBChunkExtensionValue,
+    /// Error happened in chunk complete callback
+    CBChunkComplete,
+    /// Error happened in expect continue callback
+    CBExpectContinue,
 
     // Parsing-related errors
     /// Invalid EOF state
     InvalidEofState,
     /// Header size is overflowed
     HeaderOverflow,
+    /// Request or status line size is overflowed
+    StartLineOverflow,
+    /// URL size is overflowed
+    UrlOverflow,
+    /// Number of headers is overflowed
+    HeaderCountOverflow,
+    /// Header value size is overflowed
+    HeaderValueOverflow,
     /// Connection is closed
     ClosedConnection,
     /// Invalid HTTP version
@@ -50,22 +79,209 @@ pub enum HttpErrno {
     LFExpected,
     /// Invalid header token
     InvalidHeaderToken,
+    /// Obsolete line folding in a header value
+    ObsFold,
     /// Invalid content length
     InvalidContentLength,
+    /// Content-Length header together with Transfer-Encoding
+    UnexpectedContentLength,
+    /// Repeated Content-Length header
+    DuplicateContentLength,
+    /// Content-Length header is required
+    MissingContentLength,
+    /// Invalid transfer encoding
+    InvalidTransferEncoding,
     /// Invalid chunk size
     InvalidChunkSize,
+    /// Invalid chunk extension
+    InvalidChunkExtension,
+    /// Chunk extension size is overflowed
+    ChunkExtensionOverflow,
+    /// Body size is overflowed
+    BodyTooLarge,
     /// Invalid constant
     InvalidConstant,
     /// Invalid internal state
     InvalidInternalState,
-    /// Error happened in strict mode
+    /// Protocol violation that is not tolerated by the leniency settings
     Strict,
     /// Error happened when the parser is paused
     Paused,
-    /// Unkown error
+    /// Unknown error
     Unknown,
 }
 
+impl HttpErrno {
+    /// Returns the name of the errno, as `http_errno_name` of joyent's http-parser does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// assert_eq!(HttpErrno::InvalidHeaderToken.name(), "HPE_INVALID_HEADER_TOKEN");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HttpErrno::CBMessageBegin => "HPE_CB_message_begin",
+            HttpErrno::CBUrl => "HPE_CB_url",
+            HttpErrno::CBHeaderField => "HPE_CB_header_field",
+            HttpErrno::CBHeaderValue => "HPE_CB_header_value",
+            HttpErrno::CBHeadersComplete => "HPE_CB_headers_complete",
+            HttpErrno::CBBody => "HPE_CB_body",
+            HttpErrno::CBMessageComplete => "HPE_CB_message_complete",
+            HttpErrno::CBStatus => "HPE_CB_status",
+            HttpErrno::CBTrailerField => "HPE_CB_trailer_field",
+            HttpErrno::CBTrailerValue => "HPE_CB_trailer_value",
+            HttpErrno::CBTrailersComplete => "HPE_CB_trailers_complete",
+            HttpErrno::CBChunkHeader => "HPE_CB_chunk_header",
+            HttpErrno::CBChunkExtensionName => "HPE_CB_chunk_extension_name",
+            HttpErrno::CBChunkExtensionValue => "HPE_CB_chunk_extension_value",
+            HttpErrno::CBChunkComplete => "HPE_CB_chunk_complete",
+            HttpErrno::CBExpectContinue => "HPE_CB_expect_continue",
+            HttpErrno::InvalidEofState => "HPE_INVALID_EOF_STATE",
+            HttpErrno::HeaderOverflow => "HPE_HEADER_OVERFLOW",
+            HttpErrno::StartLineOverflow => "HPE_START_LINE_OVERFLOW",
+            HttpErrno::UrlOverflow => "HPE_URL_OVERFLOW",
+            HttpErrno::HeaderCountOverflow => "HPE_HEADER_COUNT_OVERFLOW",
+            HttpErrno::HeaderValueOverflow => "HPE_HEADER_VALUE_OVERFLOW",
+            HttpErrno::ClosedConnection => "HPE_CLOSED_CONNECTION",
+            HttpErrno::InvalidVersion => "HPE_INVALID_VERSION",
+            HttpErrno::InvalidStatus => "HPE_INVALID_STATUS",
+            HttpErrno::InvalidMethod => "HPE_INVALID_METHOD",
+            HttpErrno::InvalidUrl => "HPE_INVALID_URL",
+            HttpErrno::InvalidHost => "HPE_INVALID_HOST",
+            HttpErrno::InvalidPort => "HPE_INVALID_PORT",
+            HttpErrno::InvalidPath => "HPE_INVALID_PATH",
+            HttpErrno::InvalidQueryString => "HPE_INVALID_QUERY_STRING",
+            HttpErrno::InvalidFragment => "HPE_INVALID_FRAGMENT",
+            HttpErrno::LFExpected => "HPE_LF_EXPECTED",
+            HttpErrno::InvalidHeaderToken => "HPE_INVALID_HEADER_TOKEN",
+            HttpErrno::ObsFold => "HPE_OBS_FOLD",
+            HttpErrno::InvalidContentLength => "HPE_INVALID_CONTENT_LENGTH",
+            HttpErrno::UnexpectedContentLength => "HPE_UNEXPECTED_CONTENT_LENGTH",
+            HttpErrno::DuplicateContentLength => "HPE_DUPLICATE_CONTENT_LENGTH",
+            HttpErrno::MissingContentLength => "HPE_MISSING_CONTENT_LENGTH",
+            HttpErrno::InvalidTransferEncoding => "HPE_INVALID_TRANSFER_ENCODING",
+            HttpErrno::InvalidChunkSize => "HPE_INVALID_CHUNK_SIZE",
+            HttpErrno::InvalidChunkExtension => "HPE_INVALID_CHUNK_EXTENSION",
+            HttpErrno::ChunkExtensionOverflow => "HPE_CHUNK_EXTENSION_OVERFLOW",
+            HttpErrno::BodyTooLarge => "HPE_BODY_TOO_LARGE",
+            HttpErrno::InvalidConstant => "HPE_INVALID_CONSTANT",
+            HttpErrno::InvalidInternalState => "HPE_INVALID_INTERNAL_STATE",
+            HttpErrno::Strict => "HPE_STRICT",
+            HttpErrno::Paused => "HPE_PAUSED",
+            HttpErrno::Unknown => "HPE_UNKNOWN",
+        }
+    }
+
+    /// Returns the stable numeric code of the errno.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// assert_eq!(HttpErrno::InvalidHeaderToken.code(), 24);
+    /// ```
+    pub fn code(&self) -> u32 {
+        match *self {
+            HttpErrno::CBMessageBegin => 1,
+            HttpErrno::CBUrl => 2,
+            HttpErrno::CBHeaderField => 3,
+            HttpErrno::CBHeaderValue => 4,
+            HttpErrno::CBHeadersComplete => 5,
+            HttpErrno::CBBody => 6,
+            HttpErrno::CBMessageComplete => 7,
+            HttpErrno::CBStatus => 8,
+            HttpErrno::CBTrailerField => 64,
+            HttpErrno::CBTrailerValue => 65,
+            HttpErrno::CBTrailersComplete => 66,
+            HttpErrno::CBChunkHeader => 9,
+            HttpErrno::CBChunkExtensionName => 67,
+            HttpErrno::CBChunkExtensionValue => 68,
+            HttpErrno::CBChunkComplete => 10,
+            HttpErrno::CBExpectContinue => 69,
+            HttpErrno::InvalidEofState => 11,
+            HttpErrno::HeaderOverflow => 12,
+            HttpErrno::StartLineOverflow => 96,
+            HttpErrno::UrlOverflow => 97,
+            HttpErrno::HeaderCountOverflow => 98,
+            HttpErrno::HeaderValueOverflow => 99,
+            HttpErrno::ClosedConnection => 13,
+            HttpErrno::InvalidVersion => 14,
+            HttpErrno::InvalidStatus => 15,
+            HttpErrno::InvalidMethod => 16,
+            HttpErrno::InvalidUrl => 17,
+            HttpErrno::InvalidHost => 18,
+            HttpErrno::InvalidPort => 19,
+            HttpErrno::InvalidPath => 20,
+            HttpErrno::InvalidQueryString => 21,
+            HttpErrno::InvalidFragment => 22,
+            HttpErrno::LFExpected => 23,
+            HttpErrno::InvalidHeaderToken => 24,
+            HttpErrno::ObsFold => 105,
+            HttpErrno::InvalidContentLength => 25,
+            HttpErrno::UnexpectedContentLength => 26,
+            HttpErrno::DuplicateContentLength => 103,
+            HttpErrno::MissingContentLength => 104,
+            HttpErrno::InvalidTransferEncoding => 33,
+            HttpErrno::InvalidChunkSize => 27,
+            HttpErrno::InvalidChunkExtension => 100,
+            HttpErrno::ChunkExtensionOverflow => 101,
+            HttpErrno::BodyTooLarge => 102,
+            HttpErrno::InvalidConstant => 28,
+            HttpErrno::InvalidInternalState => 29,
+            HttpErrno::Strict => 30,
+            HttpErrno::Paused => 31,
+            HttpErrno::Unknown => 32,
+        }
+    }
+
+    /// Returns true if a callback failed.
+    pub fn is_callback_error(&self) -> bool {
+        matches!(*self,
+                 HttpErrno::CBMessageBegin |
+                 HttpErrno::CBUrl |
+                 HttpErrno::CBHeaderField |
+                 HttpErrno::CBHeaderValue |
+                 HttpErrno::CBHeadersComplete |
+                 HttpErrno::CBBody |
+                 HttpErrno::CBMessageComplete |
+                 HttpErrno::CBStatus |
+                 HttpErrno::CBTrailerField |
+                 HttpErrno::CBTrailerValue |
+                 HttpErrno::CBTrailersComplete |
+                 HttpErrno::CBChunkHeader |
+                 HttpErrno::CBChunkExtensionName |
+                 HttpErrno::CBChunkExtensionValue |
+                 HttpErrno::CBChunkComplete |
+                 HttpErrno::CBExpectContinue)
+    }
+
+    /// Returns true if a limit of the `ParserSettings` is exceeded.
+    pub fn is_limit_error(&self) -> bool {
+        matches!(*self,
+                 HttpErrno::HeaderOverflow |
+                 HttpErrno::StartLineOverflow |
+                 HttpErrno::UrlOverflow |
+                 HttpErrno::HeaderCountOverflow |
+                 HttpErrno::HeaderValueOverflow |
+                 HttpErrno::ChunkExtensionOverflow |
+                 HttpErrno::BodyTooLarge)
+    }
+
+    /// Returns true if the peer sent a malformed message.
+    ///
+    /// Callback errors, exceeded limits, pausing and internal errors aren't protocol errors.
+    pub fn is_protocol_error(&self) -> bool {
+        match *self {
+            HttpErrno::Paused |
+            HttpErrno::InvalidInternalState |
+            HttpErrno::Unknown => false,
+            _ => !self.is_callback_error() && !self.is_limit_error(),
+        }
+    }
+}
+
 impl fmt::Display for HttpErrno {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -77,9 +293,21 @@ impl fmt::Display for HttpErrno {
             HttpErrno::CBBody => write!(f, "the on_body callback failed"),
             HttpErrno::CBMessageComplete => write!(f, "the on_message_complete callback failed"),
             HttpErrno::CBStatus => write!(f, "the on_status callback failed"),
+            HttpErrno::CBTrailerField => write!(f, "the on_trailer_field callback failed"),
+            HttpErrno::CBTrailerValue => write!(f, "the on_trailer_value callback failed"),
+            HttpErrno::CBTrailersComplete => write!(f, "the on_trailers_complete callback failed"),
+            HttpErrno::CBChunkHeader => write!(f, "the on_chunk_header callback failed"),
+            HttpErrno::CBChunkExtensionName => write!(f, "the on_chunk_extension_name callback failed"),
+            HttpErrno::CBChunkExtensionValue => write!(f, "the on_chunk_extension_value callback failed"),
+            HttpErrno::CBChunkComplete => write!(f, "the on_chunk_complete callback failed"),
+            HttpErrno::CBExpectContinue => write!(f, "the on_expect_continue callback failed"),
 
             HttpErrno::InvalidEofState => write!(f, "stream ended at an unexpected time"),
             HttpErrno::HeaderOverflow => write!(f, "too many header bytes seen; overflow detected"),
+            HttpErrno::StartLineOverflow => write!(f, "too many start line bytes seen; overflow detected"),
+            HttpErrno::UrlOverflow => write!(f, "too many URL bytes seen; overflow detected"),
+            HttpErrno::HeaderCountOverflow => write!(f, "too many headers seen; overflow detected"),
+            HttpErrno::HeaderValueOverflow => write!(f, "too many header value bytes seen; overflow detected"),
             HttpErrno::ClosedConnection => write!(f, "data received after completed connection: close message"),
             HttpErrno::InvalidVersion => write!(f, "invalid HTTP version"),
             HttpErrno::InvalidStatus => write!(f, "invalid HTTP status code"),
@@ -91,14 +319,60 @@ impl fmt::Display for HttpErrno {
             HttpErrno::InvalidQueryString => write!(f, "invalid query string"),
             HttpErrno::InvalidFragment => write!(f, "invalid fragment"),
             HttpErrno::LFExpected => write!(f, "LF character expected"),
-            HttpErrno::InvalidHeaderToken => write!(f, "invalid charater in header"),
+            HttpErrno::InvalidHeaderToken => write!(f, "invalid character in header"),
+            HttpErrno::ObsFold => write!(f, "obsolete line folding in header value"),
             HttpErrno::InvalidContentLength => write!(f, "invalid character in content-length header"),
+            HttpErrno::UnexpectedContentLength => write!(f, "unexpected content-length header"),
+            HttpErrno::DuplicateContentLength => write!(f, "duplicate content-length header"),
+            HttpErrno::MissingContentLength => write!(f, "missing content-length header"),
+            HttpErrno::InvalidTransferEncoding => write!(f, "message has invalid transfer-encoding"),
             HttpErrno::InvalidChunkSize => write!(f, "invalid character in chunk size header"),
+            HttpErrno::InvalidChunkExtension => write!(f, "invalid character in chunk extension"),
+            HttpErrno::ChunkExtensionOverflow => write!(f, "too many chunk extension bytes seen; overflow detected"),
+            HttpErrno::BodyTooLarge => write!(f, "body is larger than the configured maximum"),
             HttpErrno::InvalidConstant => write!(f, "invalid constant string"),
             HttpErrno::InvalidInternalState => write!(f, "encountered unexpected internal state"),
             HttpErrno::Strict => write!(f, "strict mode assertion failed"),
-            HttpErrno::Paused => write!(f, "parser is parsed"),
+            HttpErrno::Paused => write!(f, "parser is paused"),
             HttpErrno::Unknown => write!(f, "an unknown error occurred"),
         }
     }
 }
+
+impl error::Error for HttpErrno {}
+
+/// `ParseError` describes where and why parsing failed.
+///
+/// The line and column are counted from the start of the message over the start line, the
+/// headers, the chunk size lines and the trailers. Body data isn't split into lines, so an error
+/// in the body reports the column counted from the last line before the body.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    /// Error number
+    pub errno: HttpErrno,
+    /// Offset of the byte at which parsing stopped, counted from the first byte passed to the
+    /// parser. An invalid host or port of the URL is only found at the end of the host, it is
+    /// reported at the byte that made it invalid
+    pub offset: u64,
+    /// Line of the message at which parsing stopped, starting from 1
+    pub line: u64,
+    /// Column of the line at which parsing stopped, starting from 1
+    pub column: u64,
+    /// Description of the part of the message being parsed, e.g. "in header value"
+    pub state: &'static str,
+    /// Error returned by the callback, if a callback failed
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {} ({}, offset {})",
+               self.errno, self.line, self.column, self.state, self.offset)?;
+        if let Some(ref reason) = self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
@@ -1,3 +1,4 @@
+#[derive(Clone, Copy)]
 pub enum Flags {
     Chunked                 = 1isize << 0,
     ConnectionKeepAlive     = 1isize << 1,
@@ -5,10 +6,15 @@ pub enum Flags {
     Trailing                = 1isize << 3,
     Upgrade                 = 1isize << 4,
     SkipBody                = 1isize << 5,
+    ConnectionUpgrade       = 1isize << 6,
+    ExpectContinue          = 1isize << 7,
+    ExpectRejected          = 1isize << 8,
+    ContentLength           = 1isize << 9,
+    TransferEncoding        = 1isize << 10,
 }
 
 impl Flags {
-    pub fn as_u8(self) -> u8 {
-        self as u8
+    pub fn as_u16(self) -> u16 {
+        self as u16
     }
 }
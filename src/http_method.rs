@@ -1,5 +1,23 @@
+use std::fmt;
+use std::iter;
+use std::slice;
+use std::str::FromStr;
+
+use error::HttpErrno;
+
 /// `HttpMethod` defines supported HTTP methods.
-#[derive(PartialEq, Eq, Clone, Copy)]
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let method: HttpMethod = "PUT".parse().unwrap();
+///
+/// assert_eq!(method, HttpMethod::Put);
+/// assert_eq!(method.as_str(), "PUT");
+/// assert!(method.is_idempotent() && !method.is_safe());
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum HttpMethod {
     Delete,
     Get,
@@ -34,38 +52,154 @@ pub enum HttpMethod {
     Purge,
     // CalDAV
     MKCalendar,
+    /// A method registered with `ParserSettings::method`
+    Extension(&'static str),
 }
 
-impl ToString for HttpMethod {
-    fn to_string(&self) -> String {
+const METHODS: [HttpMethod; 27] = [
+    HttpMethod::Delete,
+    HttpMethod::Get,
+    HttpMethod::Head,
+    HttpMethod::Post,
+    HttpMethod::Put,
+    HttpMethod::Connect,
+    HttpMethod::Options,
+    HttpMethod::Trace,
+    HttpMethod::Copy,
+    HttpMethod::Lock,
+    HttpMethod::MKCol,
+    HttpMethod::Move,
+    HttpMethod::PropFind,
+    HttpMethod::PropPatch,
+    HttpMethod::Search,
+    HttpMethod::Unlock,
+    HttpMethod::Report,
+    HttpMethod::MKActivity,
+    HttpMethod::Checkout,
+    HttpMethod::Merge,
+    HttpMethod::MSearch,
+    HttpMethod::Notify,
+    HttpMethod::Subscribe,
+    HttpMethod::Unsubscribe,
+    HttpMethod::Patch,
+    HttpMethod::Purge,
+    HttpMethod::MKCalendar,
+];
+
+impl HttpMethod {
+    /// Returns an iterator over the methods known to the parser, without `Extension`.
+    pub fn iter() -> iter::Copied<slice::Iter<'static, HttpMethod>> {
+        METHODS.iter().copied()
+    }
+
+    /// Returns the method as it appears in the request line.
+    pub fn as_str(&self) -> &'static str {
         match *self {
-            HttpMethod::Delete      => "DELETE".to_string(),
-            HttpMethod::Get         => "GET".to_string(),
-            HttpMethod::Head        => "HEAD".to_string(),
-            HttpMethod::Post        => "POST".to_string(),
-            HttpMethod::Put         => "Put".to_string(),
-            HttpMethod::Connect     => "CONNECT".to_string(),
-            HttpMethod::Options     => "OPTIONS".to_string(),
-            HttpMethod::Trace       => "TRACE".to_string(),
-            HttpMethod::Copy        => "COPY".to_string(),
-            HttpMethod::Lock        => "LOCK".to_string(),
-            HttpMethod::MKCol       => "MKCOL".to_string(),
-            HttpMethod::Move        => "MOVE".to_string(),
-            HttpMethod::PropFind    => "PROPFIND".to_string(),
-            HttpMethod::PropPatch   => "PROPPATCH".to_string(),
-            HttpMethod::Search      => "SEARCH".to_string(),
-            HttpMethod::Unlock      => "UNLOCK".to_string(),
-            HttpMethod::Report      => "REPORT".to_string(),
-            HttpMethod::MKActivity  => "MKACTIVITY".to_string(),
-            HttpMethod::Checkout    => "CHECKOUT".to_string(),
-            HttpMethod::Merge       => "MERGE".to_string(),
-            HttpMethod::MSearch     => "M-SEARCH".to_string(),
-            HttpMethod::Notify      => "NOTIFY".to_string(),
-            HttpMethod::Subscribe   => "SUBSCRIBE".to_string(),
-            HttpMethod::Unsubscribe => "UNSUBSCRIBE".to_string(),
-            HttpMethod::Patch       => "PATCH".to_string(),
-            HttpMethod::Purge       => "PURGE".to_string(),
-            HttpMethod::MKCalendar  => "MKCALENDAR".to_string(),
+            HttpMethod::Delete      => "DELETE",
+            HttpMethod::Get         => "GET",
+            HttpMethod::Head        => "HEAD",
+            HttpMethod::Post        => "POST",
+            HttpMethod::Put         => "PUT",
+            HttpMethod::Connect     => "CONNECT",
+            HttpMethod::Options     => "OPTIONS",
+            HttpMethod::Trace       => "TRACE",
+            HttpMethod::Copy        => "COPY",
+            HttpMethod::Lock        => "LOCK",
+            HttpMethod::MKCol       => "MKCOL",
+            HttpMethod::Move        => "MOVE",
+            HttpMethod::PropFind    => "PROPFIND",
+            HttpMethod::PropPatch   => "PROPPATCH",
+            HttpMethod::Search      => "SEARCH",
+            HttpMethod::Unlock      => "UNLOCK",
+            HttpMethod::Report      => "REPORT",
+            HttpMethod::MKActivity  => "MKACTIVITY",
+            HttpMethod::Checkout    => "CHECKOUT",
+            HttpMethod::Merge       => "MERGE",
+            HttpMethod::MSearch     => "M-SEARCH",
+            HttpMethod::Notify      => "NOTIFY",
+            HttpMethod::Subscribe   => "SUBSCRIBE",
+            HttpMethod::Unsubscribe => "UNSUBSCRIBE",
+            HttpMethod::Patch       => "PATCH",
+            HttpMethod::Purge       => "PURGE",
+            HttpMethod::MKCalendar  => "MKCALENDAR",
+            HttpMethod::Extension(name) => name,
         }
     }
+
+    /// Returns true if the method is read-only, see RFC 9110 section 9.2.1.
+    ///
+    /// Extension methods aren't considered safe.
+    pub fn is_safe(&self) -> bool {
+        matches!(*self,
+                 HttpMethod::Get |
+                 HttpMethod::Head |
+                 HttpMethod::Options |
+                 HttpMethod::Trace |
+                 HttpMethod::PropFind |
+                 HttpMethod::Search |
+                 HttpMethod::Report)
+    }
+
+    /// Returns true if repeating the request has the same effect as sending it once, see
+    /// RFC 9110 section 9.2.2.
+    ///
+    /// Extension methods aren't considered idempotent.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(*self,
+                                   HttpMethod::Delete |
+                                   HttpMethod::Put |
+                                   HttpMethod::Copy |
+                                   HttpMethod::MKCol |
+                                   HttpMethod::Move |
+                                   HttpMethod::PropPatch |
+                                   HttpMethod::Unlock |
+                                   HttpMethod::MKActivity |
+                                   HttpMethod::Checkout |
+                                   HttpMethod::Purge |
+                                   HttpMethod::MKCalendar)
+    }
+
+    /// Returns true if a body in the request has a defined meaning for the method.
+    ///
+    /// The body of a GET, HEAD, DELETE or CONNECT request has no defined semantics and a TRACE
+    /// request must not have one, see RFC 9110 section 9.3. Extension methods allow a body.
+    pub fn allows_request_body(&self) -> bool {
+        !matches!(*self,
+                  HttpMethod::Get |
+                  HttpMethod::Head |
+                  HttpMethod::Delete |
+                  HttpMethod::Connect |
+                  HttpMethod::Trace |
+                  HttpMethod::Copy |
+                  HttpMethod::Move |
+                  HttpMethod::Unlock |
+                  HttpMethod::MSearch |
+                  HttpMethod::Subscribe |
+                  HttpMethod::Unsubscribe |
+                  HttpMethod::Purge)
+    }
+
+    /// Returns true if a response to the method can have a body.
+    ///
+    /// A response to HEAD has none, and a successful response to CONNECT turns the connection
+    /// into a tunnel instead.
+    pub fn expects_response_body(&self) -> bool {
+        !matches!(*self, HttpMethod::Head | HttpMethod::Connect)
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = HttpErrno;
+
+    /// Parses a method known to the parser. Methods are case-sensitive, and extension methods
+    /// fail with `HttpErrno::InvalidMethod` since they are only known to `ParserSettings`.
+    fn from_str(s: &str) -> Result<HttpMethod, HttpErrno> {
+        HttpMethod::iter().find(|method| method.as_str() == s).ok_or(HttpErrno::InvalidMethod)
+    }
 }
@@ -79,10 +79,14 @@
 
 pub use self::parser::{HttpParser, HttpParserType};
 pub use self::http_version::HttpVersion;
-pub use self::error::HttpErrno;
+pub use self::error::{HttpErrno, ParseError};
 pub use self::http_method::HttpMethod;
 pub use self::callback::{HttpParserCallback, CallbackResult, ParseAction};
 pub use self::response_type::ResponseType;
+pub use self::request_target::RequestTarget;
+pub use self::settings::{ParserSettings, Leniency, ObsFold};
+pub use self::url::{parse_url, UrlFields, UrlField};
+pub use self::decode::{Query, percent_decode_path};
 
 mod parser;
 mod http_version;
@@ -92,3 +96,7 @@ mod flags;
 mod http_method;
 mod callback;
 mod response_type;
+mod request_target;
+mod settings;
+mod url;
+mod decode;
@@ -1,15 +1,19 @@
 //! The parser that parse requests and responses.
 
-use std::u64;
 use std::cmp;
+use std::mem;
+use std::str;
 
-use state::{State, HeaderState};
+use state::{State, HeaderState, HostState};
 use flags::Flags;
-use error::HttpErrno;
+use error::{HttpErrno, ParseError};
 use http_method::HttpMethod;
 use http_version::HttpVersion;
 use callback::{HttpParserCallback, ParseAction};
 use response_type::ResponseType;
+use request_target::RequestTarget;
+use settings::{ParserSettings, ObsFold};
+use url::{parse_url_char, url_errno, is_server_state, HostParser};
 
 /// `HttpParserType` is a type specifies whether the parser is going to parse a HTTP request,
 /// response or both.
@@ -61,21 +65,45 @@ pub struct HttpParser {
     pub method: Option<HttpMethod>,        // request only
 
     /// whether the protocol is upgraded
+    ///
+    /// A request is upgraded by CONNECT or by an Upgrade header with `Connection: upgrade`.
+    /// A response is upgraded by the status 101, or by a 2xx status if `method` was set to
+    /// CONNECT before the response is parsed.
     pub upgrade: bool,
 
-    // TODO make it as a constructor parameter?
-    /// whether using strict parsing mode
-    pub strict: bool,      // parsing using strict rules
-
     // private
+    settings: ParserSettings,
     tp: HttpParserType,
     state: State,
     header_state: HeaderState,
-    flags: u8,
+    flags: u16,
     index: usize,             // index into current matcher
 
     nread: usize,            // bytes read in various scenarios
     content_length: u64,   // bytes in body (0 if no Content-Length header)
+    announced_content_length: Option<u64>, // value of the Content-Length header
+    upgrade_protocol: Vec<u8>, // value of the Upgrade headers
+    transfer_encoding: Vec<u8>, // value of the Transfer-Encoding headers
+    connection: Vec<u8>,     // value of the Connection headers
+    method_token: Vec<u8>,   // method of the request, if extension methods are set
+    unfold_ows: Vec<u8>,     // whitespace held back while unfolding a header value
+
+    request_target: Option<RequestTarget>, // form of the request URL
+    url_len: usize,          // bytes in the request URL
+    host_parser: HostParser, // validates the host and port of the request URL
+    host_error_offset: Option<u64>, // offset of the byte the host parser failed at
+    nheaders: usize,         // header fields seen in the message
+    body_len: u64,           // body bytes announced or read in the message
+    header_value_len: usize, // bytes in the current header value
+    chunk_extension_len: usize, // bytes in the extensions of the current chunk
+
+    offset: u64,             // bytes consumed by previous calls of execute
+    line: u64,               // line of the message being parsed
+    line_offset: u64,        // offset of the first byte of the line
+    next_line_offset: u64,   // offset of the byte after the last LF seen
+    error_offset: Option<u64>, // offset of the byte at fault if the error is found later
+    error: Option<ParseError>,
+    callback_error: Option<String>, // message returned by the callback that failed; cleared on reset
 }
 
 //============== End of public interfaces ===================
@@ -85,7 +113,11 @@ macro_rules! callback(
     ($parser:ident, $cb:expr, $err:expr, $idx:expr) => (
        assert!($parser.errno.is_none());
        match $cb {
-           Err(..) => $parser.errno = Option::Some($err),
+           Err(reason) => {
+               $parser.errno = Option::Some($err);
+               $parser.callback_error = Option::Some(reason);
+           },
+           Ok(ParseAction::Pause) => $parser.errno = Option::Some(HttpErrno::Paused),
            _ => (),
        }
 
@@ -95,16 +127,78 @@ macro_rules! callback(
     );
 );
 
-/// A macro that returns parsing error if it is in strict mode and the condition is not met.
+/// A macro that calls the header or the trailer field callback, depending on whether the
+/// trailer of a chunked message is being parsed.
+macro_rules! header_field_callback(
+    ($parser:ident, $cb:ident, $data:expr, $idx:expr) => (
+        if ($parser.flags & Flags::Trailing.as_u16()) != 0 {
+            callback!($parser, $cb.on_trailer_field($parser, $data),
+                      HttpErrno::CBTrailerField, $idx);
+        } else {
+            callback!($parser, $cb.on_header_field($parser, $data),
+                      HttpErrno::CBHeaderField, $idx);
+        }
+    );
+);
+
+/// A macro that calls the header or the trailer value callback, depending on whether the
+/// trailer of a chunked message is being parsed.
+macro_rules! header_value_callback(
+    ($parser:ident, $cb:ident, $data:expr, $idx:expr) => (
+        if ($parser.flags & Flags::Trailing.as_u16()) != 0 {
+            callback!($parser, $cb.on_trailer_value($parser, $data),
+                      HttpErrno::CBTrailerValue, $idx);
+        } else {
+            callback!($parser, $cb.on_header_value($parser, $data),
+                      HttpErrno::CBHeaderValue, $idx);
+        }
+    );
+);
+
+/// A macro that returns parsing error if the condition is met and the given leniency is not
+/// enabled.
 macro_rules! strict_check(
-    ($parser:ident, $cond:expr, $idx:expr) => (
-        if $parser.strict && $cond {
+    ($parser:ident, $leniency:ident, $cond:expr, $idx:expr) => (
+        if !$parser.settings.leniency.$leniency && $cond {
             $parser.errno = Option::Some(HttpErrno::Strict);
             return $idx;
         }
     );
 );
 
+/// A macro that fails with `HttpErrno::LFExpected` unless the byte after a CR is LF. A bare CR
+/// never ends a line, whatever the leniency.
+macro_rules! lf_check(
+    ($parser:ident, $ch:expr, $idx:expr) => (
+        if $ch != LF {
+            $parser.errno = Option::Some(HttpErrno::LFExpected);
+            return $idx;
+        }
+    );
+);
+
+/// A macro that counts a byte of the request URL and fails if the URL is too long.
+macro_rules! url_len_check(
+    ($parser:ident, $idx:expr) => (
+        $parser.url_len += 1;
+        if $parser.url_len > $parser.settings.max_url_size {
+            $parser.errno = Option::Some(HttpErrno::UrlOverflow);
+            return $idx;
+        }
+    );
+);
+
+/// A macro that counts a byte of a header value and fails if the value is too long.
+macro_rules! header_value_len_check(
+    ($parser:ident, $idx:expr) => (
+        $parser.header_value_len += 1;
+        if $parser.header_value_len > $parser.settings.max_header_value_size {
+            $parser.errno = Option::Some(HttpErrno::HeaderValueOverflow);
+            return $idx;
+        }
+    );
+);
+
 /// A macro that marks the index for any marker
 macro_rules! mark(
     ($mark:ident, $idx:expr) => (
@@ -114,51 +208,134 @@ macro_rules! mark(
     );
 );
 
-const HTTP_MAX_HEADER_SIZE: usize = 80*1024;
 const ULLONG_MAX: u64 = u64::MAX;
 
 const CR: u8 = b'\r';
 const LF: u8 = b'\n';
 
-const PROXY_CONNECTION: &'static str = "proxy-connection";
-const CONNECTION: &'static str = "connection";
-const CONTENT_LENGTH: &'static str = "content-length";
-const TRANSFER_ENCODING: &'static str = "transfer-encoding";
-const UPGRADE: &'static str = "upgrade";
-const CHUNKED: &'static str = "chunked";
-const KEEP_ALIVE: &'static str = "keep-alive";
-const CLOSE: &'static str = "close";
+const PROXY_CONNECTION: &str = "proxy-connection";
+const CONNECTION: &str = "connection";
+const CONTENT_LENGTH: &str = "content-length";
+const TRANSFER_ENCODING: &str = "transfer-encoding";
+const UPGRADE: &str = "upgrade";
+const EXPECT: &str = "expect";
+const CONTINUE: &str = "100-continue";
+const CHUNKED: &str = "chunked";
+const KEEP_ALIVE: &str = "keep-alive";
+const CLOSE: &str = "close";
+
+// Characters of a header name, the tchar of RFC 9110 section 5.6.2
+static NORMAL_HEADER_CHARS: [bool; 256] = [
+/*   0 nul    1 soh    2 stx    3 etx    4 eot    5 enq    6 ack    7 bel */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*   8 bs     9 ht    10 nl    11 vt    12 np    13 cr    14 so    15 si */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  16 dle   17 dc1   18 dc2   19 dc3   20 dc4   21 nak   22 syn   23 etb */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  24 can   25 em    26 sub   27 esc   28 fs    29 gs    30 rs    31 us */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  32 sp    33 !     34 "     35 #     36 $     37 %     38 &     39 ' */
+   false,   true,    false,   true,    true,    true,    true,    true,
+/*  40 (     41 )     42 *     43 +     44 ,     45 -     46 .     47 / */
+   false,   false,   true,    true,    false,   true,    true,    false,
+/*  48 0     49 1     50 2     51 3     52 4     53 5     54 6     55 7 */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  56 8     57 9     58 :     59 ;     60 <     61 =     62 >     63 ? */
+   true,    true,    false,   false,   false,   false,   false,   false,
+/*  64 @     65 A     66 B     67 C     68 D     69 E     70 F     71 G */
+   false,   true,    true,    true,    true,    true,    true,    true,
+/*  72 H     73 I     74 J     75 K     76 L     77 M     78 N     79 O */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  80 P     81 Q     82 R     83 S     84 T     85 U     86 V     87 W */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  88 X     89 Y     90 Z     91 [     92 \     93 ]     94 ^     95 _ */
+   true,    true,    true,    false,   false,   false,   true,    true,
+/*  96 `     97 a     98 b     99 c    100 d    101 e    102 f    103 g */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 104 h    105 i    106 j    107 k    108 l    109 m    110 n    111 o */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 112 p    113 q    114 r    115 s    116 t    117 u    118 v    119 w */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 120 x    121 y    122 z    123 {    124 |    125 }    126 ~    127 del */
+   true,    true,    true,    false,   true,    false,   true,    false,
+/* 128-255 */
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+];
 
 fn is_normal_header_char(ch: u8) -> bool {
-    ch == b'!' || (ch >= b'#' && ch <= b'\'') /* #, $, %, &, ' */||
-        ch == b'*' || ch == b'+' || ch == b'-' || ch == b'.' ||
-        (ch >= b'0' && ch <= b'9') /* 0-9 */ || (ch >= b'A' && ch <= b'Z') /* A-Z */ ||
-        (ch >= b'^' && ch <= b'z') /* ^, _, `, a-z */ || ch == b'|' || ch == b'~'
+    NORMAL_HEADER_CHARS[ch as usize]
 }
 
-fn is_header_char(strict: bool, ch: u8) -> bool {
-    if strict {
-        is_normal_header_char(ch)
-    } else {
+fn is_header_char(lenient: bool, ch: u8) -> bool {
+    if lenient {
         ch == b' ' || is_normal_header_char(ch)
+    } else {
+        is_normal_header_char(ch)
+    }
+}
+
+// Splits a comma-separated header value into its trimmed, non-empty elements
+fn split_list(value: &[u8]) -> Vec<&str> {
+    match str::from_utf8(value) {
+        Ok(value) => value.split(',')
+                          .map(|element| element.trim())
+                          .filter(|element| !element.is_empty())
+                          .collect(),
+        Err(_) => Vec::new(),
     }
 }
 
-fn is_normal_url_char(ch: u8) -> bool {
-    // refer to http_parser.c or ascii table for characters
-    ch == b'!' || ch == b'"' || (ch >= b'$' && ch <= b'>') || (ch >= b'@' && ch <= b'~')
+// Returns the header state at the first character of a Connection token. `ch` isn't
+// lowercased, `lower` would turn a tab into another character
+fn connection_token_start(ch: u8) -> HeaderState {
+    match ch {
+        b' ' | b'\t' | b',' => HeaderState::MatchingConnectionTokenStart,
+        _ => match lower(ch) {
+            b'k' => HeaderState::MatchingConnectionKeepAlive,
+            b'c' => HeaderState::MatchingConnectionClose,
+            b'u' => HeaderState::MatchingConnectionUpgrade,
+            _ => HeaderState::MatchingConnectionToken,
+        },
+    }
 }
 
-fn is_url_char(strict: bool, ch: u8) -> bool {
-    is_normal_url_char(ch) || (!strict && ((ch & 0x80) > 0 || ch == 9 || ch == 12))
+// Matches the character at `index` of a known Connection token
+fn match_connection_token(token: &str, index: usize, c: u8,
+                          matching: HeaderState, matched: HeaderState) -> HeaderState {
+    if index >= token.len() || c != token.as_bytes()[index] {
+        if c == b',' {
+            HeaderState::MatchingConnectionTokenStart
+        } else {
+            HeaderState::MatchingConnectionToken
+        }
+    } else if index == token.len()-1 {
+        matched
+    } else {
+        matching
+    }
 }
 
-fn unhex_value(ch: u8) -> Option<i32> {
-    if ch >= b'0' && ch <= b'9' {
+pub fn unhex_value(ch: u8) -> Option<i32> {
+    if ch.is_ascii_digit() {
         Option::Some((ch - b'0') as i32)
-    } else if ch >= b'a' && ch <= b'f' {
+    } else if (b'a'..=b'f').contains(&ch) {
         Option::Some((10 + ch - b'a') as i32)
-    } else if ch >= b'A' && ch <= b'F' {
+    } else if (b'A'..=b'F').contains(&ch) {
         Option::Some((10 + ch - b'A') as i32)
     } else {
         Option::None
@@ -169,29 +346,18 @@ fn lower(ch: u8) -> u8 {
     ch | 0x20
 }
 
-fn is_num(ch: u8) -> bool {
-    ch >= b'0' && ch <= b'9'
+pub fn is_num(ch: u8) -> bool {
+    ch.is_ascii_digit()
 }
 
-fn is_alpha(ch: u8) -> bool {
-    (ch >= b'a' && ch <= b'z') || (ch >= b'A' && ch <= b'Z')
+pub fn is_alpha(ch: u8) -> bool {
+    ch.is_ascii_alphabetic()
 }
 
-fn is_alphanum(ch: u8) -> bool {
+pub fn is_alphanum(ch: u8) -> bool {
     is_num(ch) || is_alpha(ch)
 }
 
-fn is_mark(ch: u8) -> bool {
-    ch == b'-' || ch == b'_' || ch == b'.' || ch == b'!' || ch == b'~' ||
-        ch == b'*' || ch == b'\'' || ch == b'(' || ch == b')'
-}
-
-fn is_userinfo_char(ch: u8) -> bool {
-    is_alphanum(ch) || is_mark(ch) || ch == b'%' ||
-        ch == b';' || ch == b':' || ch == b'&' || ch == b'=' ||
-        ch == b'+' || ch == b'$' || ch == b','
-}
-
 impl HttpParser {
     /// Creates a parser of the specified type.
     ///
@@ -202,8 +368,22 @@ impl HttpParser {
     /// let mut parser = HttpParser::new(HttpParserType::Request);
     /// ```
     pub fn new(tp: HttpParserType) -> HttpParser {
+        HttpParser::with_settings(tp, ParserSettings::new())
+    }
+
+    /// Creates a parser of the specified type with custom settings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// let settings = ParserSettings::new().max_header_size(8 * 1024);
+    /// let mut parser = HttpParser::with_settings(HttpParserType::Request, settings);
+    /// ```
+    pub fn with_settings(tp: HttpParserType, settings: ParserSettings) -> HttpParser {
         HttpParser {
-            tp: tp,
+            settings,
+            tp,
             state: match tp {
                         HttpParserType::Request     => State::StartReq,
                         HttpParserType::Response    => State::StartRes,
@@ -214,18 +394,40 @@ impl HttpParser {
             index: 0,
             nread: 0,
             content_length: ULLONG_MAX,
+            announced_content_length: Option::None,
+            upgrade_protocol: Vec::new(),
+            transfer_encoding: Vec::new(),
+            connection: Vec::new(),
+            method_token: Vec::new(),
+            unfold_ows: Vec::new(),
+            request_target: Option::None,
+            url_len: 0,
+            host_parser: HostParser::new(false),
+            host_error_offset: Option::None,
+            nheaders: 0,
+            body_len: 0,
+            header_value_len: 0,
+            chunk_extension_len: 0,
+            offset: 0,
+            line: 1,
+            line_offset: 0,
+            next_line_offset: 0,
+            error_offset: Option::None,
+            error: Option::None,
+            callback_error: Option::None,
             http_version: HttpVersion { major: 1, minor: 0 },
             errno: Option::None,
             status_code: Option::None,
             response_type: Option::None,
             method: Option::None,
             upgrade: false,
-            strict: true,
         }
     }
 
     /// Parses the HTTP requests or responses, specified in `data` as an array of bytes.
     ///
+    /// An empty `data` signals the end of the stream, like `finish` does.
+    ///
     /// # Example
     ///
     /// ```
@@ -248,6 +450,89 @@ impl HttpParser {
     /// parser.execute(&mut cb, line.as_bytes());
     /// ```
     pub fn execute<T: HttpParserCallback>(&mut self, cb: &mut T, data: &[u8]) -> usize {
+        let parsed = self.execute_bytes(cb, data);
+
+        if let Some(errno) = self.errno {
+            if errno != HttpErrno::Paused && self.error.is_none() {
+                let offset = self.error_offset.take().unwrap_or(self.offset + parsed as u64);
+                let mut error = self.error_at(errno, offset);
+                error.reason = self.callback_error.take();
+                self.error = Option::Some(error);
+            }
+        }
+
+        self.offset += parsed as u64;
+        parsed
+    }
+
+    /// Signals the end of the stream.
+    ///
+    /// It completes a response whose body is read until the connection is closed, and fails
+    /// with `HttpErrno::InvalidEofState` if the stream ends in the middle of a message. A paused
+    /// parser fails with `HttpErrno::Paused` and has to be resumed first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let mut parser = HttpParser::new(HttpParserType::Response);
+    /// let mut cb = Callback;
+    ///
+    /// parser.execute(&mut cb, b"HTTP/1.1 200 OK\r\n\r\nbody until EOF");
+    /// assert!(parser.finish(&mut cb).is_ok());
+    /// ```
+    pub fn finish<T: HttpParserCallback>(&mut self, cb: &mut T) -> Result<(), ParseError> {
+        if self.is_paused() {
+            return Err(self.error_at(HttpErrno::Paused, self.offset));
+        }
+
+        self.execute(cb, &[]);
+
+        match self.error {
+            Some(ref error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Resets the parser to parse a new stream of the specified type.
+    ///
+    /// All state is cleared, including any error, while the settings are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// let mut cb = Callback;
+    ///
+    /// parser.execute(&mut cb, b"NOT HTTP\r\n");
+    /// assert!(parser.errno.is_some());
+    ///
+    /// parser.reset(HttpParserType::Request);
+    /// parser.execute(&mut cb, b"GET / HTTP/1.1\r\n\r\n");
+    /// assert!(parser.errno.is_none());
+    /// ```
+    pub fn reset(&mut self, tp: HttpParserType) {
+        let settings = self.settings.clone();
+        *self = HttpParser::with_settings(tp, settings);
+    }
+
+    fn error_at(&self, errno: HttpErrno, offset: u64) -> ParseError {
+        ParseError {
+            errno,
+            offset,
+            line: self.line,
+            column: offset - self.line_offset + 1,
+            state: self.state.name(),
+            reason: Option::None,
+        }
+    }
+
+    fn execute_bytes<T: HttpParserCallback>(&mut self, cb: &mut T, data: &[u8]) -> usize {
         let len: usize = data.len();
         let mut index: usize = 0;
         let mut header_field_mark: Option<usize> = Option::None;
@@ -255,6 +540,8 @@ impl HttpParser {
         let mut url_mark: Option<usize> = Option::None;
         let mut body_mark: Option<usize> = Option::None;
         let mut status_mark: Option<usize> = Option::None;
+        let mut chunk_extension_name_mark: Option<usize> = Option::None;
+        let mut chunk_extension_value_mark: Option<usize> = Option::None;
 
         if self.errno.is_some() {
             return 0;
@@ -263,6 +550,7 @@ impl HttpParser {
         if len == 0 {    // mean EOF
             match self.state {
                 State::BodyIdentityEof => {
+                    self.new_message();
                     callback!(self, cb.on_message_complete(self),
                               HttpErrno::CBMessageComplete, index);
                     return 0;
@@ -285,7 +573,7 @@ impl HttpParser {
         if self.state == State::HeaderField {
             header_field_mark = Option::Some(0);
         }
-        if self.state == State::HeaderValue {
+        if self.state == State::HeaderValue && self.unfold_ows.is_empty() {
             header_value_mark = Option::Some(0);
         }
         match self.state {
@@ -301,6 +589,10 @@ impl HttpParser {
             State::ReqFragmentStart |
             State::ReqFragment => url_mark = Option::Some(0),
             State::ResStatus => status_mark = Option::Some(0),
+            State::ChunkExtensionName => chunk_extension_name_mark = Option::Some(0),
+            State::ChunkExtensionValue |
+            State::ChunkExtensionQuotedValue |
+            State::ChunkExtensionQuotedValueEscape => chunk_extension_value_mark = Option::Some(0),
             _ => (),
         }
 
@@ -309,21 +601,50 @@ impl HttpParser {
             if self.state.is_header_state() {
                 self.nread += 1;
 
+                // Count lines for error reporting. A byte is processed twice when parsing
+                // resumes at it, so only the first LF at an offset starts a new line.
+                let offset = self.offset + index as u64;
+                if self.next_line_offset > self.line_offset && offset >= self.next_line_offset {
+                    self.line += 1;
+                    self.line_offset = self.next_line_offset;
+                }
+                if ch == LF {
+                    self.next_line_offset = offset + 1;
+                }
+
                 // Comments from http_parser.c:
                 // Don't allow the total size of the HTTP headers (including the status
-                // line) to exceed HTTP_MAX_HEADER_SIZE. This check is here to protect
+                // line) to exceed max_header_size. This check is here to protect
                 // embedders against denial-of-service attacks where the attacker feeds
                 // us a never-ending header that the embedder keeps buffering.
                 //
                 // This check is arguably the responsibility of embedders but we're doing
                 // it on the embedder's behalf because most won't bother and this way we
-                // make the web a little safer. HTTP_MAX_HEADER_SIZE is still far bigger
-                // than any reasonable request or response so this should never affect
-                // day-to-day operation.
-                if self.nread > HTTP_MAX_HEADER_SIZE {
+                // make the web a little safer. The default max_header_size is still far
+                // bigger than any reasonable request or response so this should never
+                // affect day-to-day operation.
+                if self.nread > self.settings.max_header_size {
                     self.errno = Option::Some(HttpErrno::HeaderOverflow);
                     return index;
                 }
+
+                if self.state < State::HeaderFieldStart &&
+                    self.nread > self.settings.max_start_line_size {
+                    self.errno = Option::Some(HttpErrno::StartLineOverflow);
+                    return index;
+                }
+            }
+
+            if (self.state.is_chunk_extension_state() ||
+                       (self.state == State::ChunkSize && unhex_value(ch).is_none())) &&
+                ch != CR {
+                // Chunk extensions are read until the end of the chunk size line, so bound
+                // them like the headers
+                self.chunk_extension_len += 1;
+                if self.chunk_extension_len > self.settings.max_chunk_extension_size {
+                    self.errno = Option::Some(HttpErrno::ChunkExtensionOverflow);
+                    return index;
+                }
             }
 
             // using loop to simulate 'goto reexecute_byte' in http_parser.c
@@ -338,8 +659,7 @@ impl HttpParser {
                     },
                     State::StartReqOrRes => {
                         if ch != CR && ch != LF {
-                            self.flags = 0;
-                            self.content_length = ULLONG_MAX;
+                            self.start_message(index);
 
                             if ch == b'H' {
                                 self.state = State::ResOrRespH;
@@ -363,13 +683,15 @@ impl HttpParser {
 
                             self.tp = HttpParserType::Request;
                             self.method = Option::Some(HttpMethod::Head);
+                            if !self.settings.methods.is_empty() {
+                                self.method_token.extend_from_slice(b"HE");
+                            }
                             self.index = 2;
                             self.state = State::ReqMethod;
                         }
                     },
                     State::StartRes => {
-                        self.flags = 0;
-                        self.content_length = ULLONG_MAX;
+                        self.start_message(index);
 
                         match ch {
                             b'H' => self.state = State::ResH,
@@ -384,40 +706,40 @@ impl HttpParser {
                         callback!(self, cb.on_message_begin(self), HttpErrno::CBMessageBegin, index+1);
                     },
                     State::ResH => {
-                        strict_check!(self, ch != b'T', index);
+                        strict_check!(self, protocol, ch != b'T', index);
                         self.state = State::ResHT;
                     },
                     State::ResHT => {
-                        strict_check!(self, ch != b'T', index);
+                        strict_check!(self, protocol, ch != b'T', index);
                         self.state = State::ResHTT;
                     },
                     State::ResHTT => {
-                        strict_check!(self, ch != b'P', index);
+                        strict_check!(self, protocol, ch != b'P', index);
                         self.state = State::ResHTTP;
                     },
                     State::ResHTTP => {
-                        strict_check!(self, ch != b'/', index);
+                        strict_check!(self, protocol, ch != b'/', index);
                         self.response_type = Some(ResponseType::Http);
                         self.state = State::ResFirstHttpMajor;
                     },
                     State::ResE => {
-                        strict_check!(self, ch != b'V', index);
+                        strict_check!(self, protocol, ch != b'V', index);
                         self.state = State::ResEV;
                     }
                     State::ResEV => {
-                        strict_check!(self, ch != b'E', index);
+                        strict_check!(self, protocol, ch != b'E', index);
                         self.state = State::ResEVE;
                     }
                     State::ResEVE => {
-                        strict_check!(self, ch != b'N', index);
+                        strict_check!(self, protocol, ch != b'N', index);
                         self.state = State::ResEVEN;
                     }
                     State::ResEVEN => {
-                        strict_check!(self, ch != b'T', index);
+                        strict_check!(self, protocol, ch != b'T', index);
                         self.state = State::ResEVENT;
                     }
                     State::ResEVENT => {
-                        strict_check!(self, ch != b'/', index);
+                        strict_check!(self, protocol, ch != b'/', index);
                         self.response_type = Some(ResponseType::Event);
                         self.state = State::ResFirstHttpMajor;
                     }
@@ -491,7 +813,10 @@ impl HttpParser {
                             match ch {
                                 b' ' => self.state = State::ResStatusStart,
                                 CR   => self.state = State::ResLineAlmostDone,
-                                LF   => self.state = State::HeaderFieldStart,
+                                LF   => {
+                                    strict_check!(self, bare_lf, ch == LF, index);
+                                    self.state = State::HeaderFieldStart;
+                                },
                                 _    => {
                                     self.errno = Option::Some(HttpErrno::InvalidStatus);
                                     return index;
@@ -513,6 +838,7 @@ impl HttpParser {
                         if ch == CR {
                             self.state = State::ResLineAlmostDone;
                         } else if ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = State::HeaderFieldStart;
                         } else {
                             mark!(status_mark, index);
@@ -522,27 +848,26 @@ impl HttpParser {
                     },
                     State::ResStatus => {
                         if ch == CR || ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = if ch == CR { State::ResLineAlmostDone } else { State::HeaderFieldStart };
-                            if status_mark.is_some() {
+                            if let Some(mark) = status_mark {
                                 callback!(self,
-                                    cb.on_status(self, &data[status_mark.unwrap() .. index]),
+                                    cb.on_status(self, &data[mark .. index]),
                                     HttpErrno::CBStatus, index+1);
                                 status_mark = Option::None;
                             }
                         }
                     },
                     State::ResLineAlmostDone => {
-                        strict_check!(self, ch != LF, index);
+                        lf_check!(self, ch, index);
                         self.state = State::HeaderFieldStart;
                     },
                     State::StartReq => {
                         if ch != CR && ch != LF {
-                            self.flags = 0;
-                            self.content_length = ULLONG_MAX;
+                            self.start_message(index);
 
-                            if !is_alpha(ch) {
-                                self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                return index;
+                            if !self.settings.methods.is_empty() {
+                                self.method_token.push(ch);
                             }
 
                             match ch {
@@ -560,8 +885,10 @@ impl HttpParser {
                                 b'T' => self.method = Option::Some(HttpMethod::Trace),
                                 b'U' => self.method = Option::Some(HttpMethod::Unlock), // or Unsubscribe
                                 _ => {
-                                    self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                    return index;
+                                    if !self.match_extension_method(ch) {
+                                        self.errno = Option::Some(HttpErrno::InvalidMethod);
+                                        return index;
+                                    }
                                 },
                             }
                             self.index = 1;
@@ -572,75 +899,15 @@ impl HttpParser {
                         }
                     },
                     State::ReqMethod => {
-                        let matcher = self.method.unwrap().to_string();
-                        if ch == b' ' && self.index == matcher.len() {
-                            self.state = State::ReqSpacesBeforeUrl;
-                        } else if self.index < matcher.len() && ch == (matcher[self.index ..].bytes().next().unwrap()) {
-                            //noop
-                        } else if self.method == Option::Some(HttpMethod::Connect) {
-                            if self.index == 1 && ch == b'H' {
-                                self.method = Option::Some(HttpMethod::Checkout);
-                            } else if self.index == 2 && ch == b'P' {
-                                self.method = Option::Some(HttpMethod::Copy);
-                            } else {
-                                self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                return index;
-                            }
-                        } else if self.method == Option::Some(HttpMethod::MKCol) {
-                            if self.index == 1 && ch == b'O' {
-                                self.method = Option::Some(HttpMethod::Move);
-                            } else if self.index == 1 && ch == b'E' {
-                                self.method = Option::Some(HttpMethod::Merge);
-                            } else if self.index == 1 && ch == b'-' {
-                                self.method = Option::Some(HttpMethod::MSearch);
-                            } else if self.index == 2 && ch == b'A' {
-                                self.method = Option::Some(HttpMethod::MKActivity);
-                            } else if self.index == 3 && ch == b'A' {
-                                self.method = Option::Some(HttpMethod::MKCalendar);
-                            } else {
-                                self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                return index;
-                            }
-                        } else if self.method == Option::Some(HttpMethod::Subscribe) {
-                            if self.index == 1 && ch == b'E' {
-                                self.method = Option::Some(HttpMethod::Search);
-                            } else {
-                                self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                return index;
-                            }
-                        } else if self.index == 1 && self.method == Option::Some(HttpMethod::Post) {
-                           if ch == b'R' {
-                               self.method = Option::Some(HttpMethod::PropFind); // or PropPatch
-                           } else if ch == b'U' {
-                               self.method = Option::Some(HttpMethod::Put); // or Purge
-                           } else if ch == b'A' {
-                               self.method = Option::Some(HttpMethod::Patch);
-                           } else {
-                               self.errno = Option::Some(HttpErrno::InvalidMethod);
-                               return index;
-                           }
-                        } else if self.index == 2 {
-                            if self.method == Option::Some(HttpMethod::Put) {
-                                if ch == b'R' {
-                                    self.method = Option::Some(HttpMethod::Purge);
-                                } else {
-                                    self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                    return index;
-                                }
-                            } else if self.method == Option::Some(HttpMethod::Unlock) {
-                                if ch == b'S' {
-                                    self.method = Option::Some(HttpMethod::Unsubscribe);
-                                } else {
-                                    self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                    return index;
-                                }
-                            } else {
-                                self.errno = Option::Some(HttpErrno::InvalidMethod);
-                                return index;
-                            }
-                        } else if self.index == 4 && self.method == Option::Some(HttpMethod::PropFind) && ch == b'P' {
-                            self.method = Option::Some(HttpMethod::PropPatch);
-                        } else {
+                        if ch != b' ' && !self.settings.methods.is_empty() {
+                            self.method_token.push(ch);
+                        }
+
+                        let matched = match self.method {
+                            Some(HttpMethod::Extension(_)) | None => false,
+                            Some(_) => self.match_method_char(ch),
+                        };
+                        if !matched && !self.match_extension_method(ch) {
                             self.errno = Option::Some(HttpErrno::InvalidMethod);
                             return index;
                         }
@@ -650,13 +917,29 @@ impl HttpParser {
                     State::ReqSpacesBeforeUrl => {
                         if ch != b' ' {
                             mark!(url_mark, index);
-                            if self.method == Option::Some(HttpMethod::Connect) {
-                                self.state = State::ReqServerStart;
-                            }
+                            self.url_len = 0;
+                            self.host_parser = HostParser::new(false);
+                            self.host_error_offset = Option::None;
+                            url_len_check!(self, index);
+                            self.request_target = Option::Some(
+                                if self.method == Option::Some(HttpMethod::Connect) {
+                                    self.state = State::ReqServerStart;
+                                    RequestTarget::Authority
+                                } else if ch == b'/' {
+                                    RequestTarget::Origin
+                                } else if ch == b'*' {
+                                    // The asterisk-form only applies to OPTIONS, see RFC 9112
+                                    // section 3.2.4
+                                    strict_check!(self, request_target,
+                                                  self.method != Option::Some(HttpMethod::Options),
+                                                  index);
+                                    RequestTarget::Asterisk
+                                } else {
+                                    RequestTarget::Absolute
+                                });
 
-                            self.state = HttpParser::parse_url_char(self, self.state, ch);
-                            if self.state == State::Dead {
-                                self.errno = Option::Some(HttpErrno::InvalidUrl);
+                            if let Err(errno) = self.next_url_state(ch, index) {
+                                self.errno = Option::Some(errno);
                                 return index;
                             }
                         }
@@ -668,13 +951,13 @@ impl HttpParser {
                         match ch {
                             // No whitespace allowed here
                             b' ' | CR | LF => {
-                                self.errno = Option::Some(HttpErrno::InvalidUrl);
+                                self.errno = Option::Some(url_errno(self.state));
                                 return index;
                             },
                             _ => {
-                                self.state = HttpParser::parse_url_char(self, self.state, ch);
-                                if self.state == State::Dead {
-                                    self.errno = Option::Some(HttpErrno::InvalidUrl);
+                                url_len_check!(self, index);
+                                if let Err(errno) = self.next_url_state(ch, index) {
+                                    self.errno = Option::Some(errno);
                                     return index;
                                 }
                             }
@@ -687,17 +970,31 @@ impl HttpParser {
                     State::ReqQueryString |
                     State::ReqFragmentStart |
                     State::ReqFragment => {
+                        if ch == b' ' || ch == CR || ch == LF {
+                            if let Err(errno) = self.end_url_server(self.state) {
+                                self.errno = Option::Some(errno);
+                                return index;
+                            }
+
+                            // The authority-form has a port, see RFC 9112 section 3.2.3
+                            strict_check!(self, request_target,
+                                          self.request_target == Option::Some(RequestTarget::Authority) &&
+                                          !matches!(self.host_parser.finish(), Ok(Some(_))),
+                                          index);
+                        }
+
                         match ch {
                             b' ' => {
                                 self.state = State::ReqHttpStart;
-                                if url_mark.is_some() {
+                                if let Some(mark) = url_mark {
                                     callback!(self,
-                                        cb.on_url(self, &data[url_mark.unwrap() .. index]),
+                                        cb.on_url(self, &data[mark .. index]),
                                         HttpErrno::CBUrl, index+1);
                                     url_mark = Option::None;
                                 }
                             },
                             CR | LF => {
+                                strict_check!(self, bare_lf, ch == LF, index);
                                 self.http_version.major = 0;
                                 self.http_version.minor = 9;
                                 self.state = if ch == CR {
@@ -705,19 +1002,27 @@ impl HttpParser {
                                 } else {
                                     State::HeaderFieldStart
                                 };
-                                if url_mark.is_some() {
+                                if let Some(mark) = url_mark {
                                     callback!(self,
-                                        cb.on_url(self, &data[url_mark.unwrap() .. index]),
+                                        cb.on_url(self, &data[mark .. index]),
                                         HttpErrno::CBUrl, index+1);
                                     url_mark = Option::None;
                                 }
                             },
                             _ => {
-                                self.state = HttpParser::parse_url_char(self, self.state, ch);
-                                if self.state == State::Dead {
-                                    self.errno = Option::Some(HttpErrno::InvalidUrl);
+                                url_len_check!(self, index);
+                                strict_check!(self, request_target,
+                                              self.request_target == Option::Some(RequestTarget::Asterisk),
+                                              index);
+                                if let Err(errno) = self.next_url_state(ch, index) {
+                                    self.errno = Option::Some(errno);
                                     return index;
                                 }
+                                // The authority-form is only host:port
+                                strict_check!(self, request_target,
+                                              self.request_target == Option::Some(RequestTarget::Authority) &&
+                                              self.state != State::ReqServer,
+                                              index);
                             }
                         }
                     },
@@ -732,24 +1037,24 @@ impl HttpParser {
                         }
                     },
                     State::ReqHttpH => {
-                        strict_check!(self, ch != b'T', index);
+                        strict_check!(self, protocol, ch != b'T', index);
                         self.state = State::ReqHttpHT;
                     },
                     State::ReqHttpHT => {
-                        strict_check!(self, ch != b'T', index);
+                        strict_check!(self, protocol, ch != b'T', index);
                         self.state = State::ReqHttpHTT;
                     },
                     State::ReqHttpHTT => {
-                        strict_check!(self, ch != b'P', index);
+                        strict_check!(self, protocol, ch != b'P', index);
                         self.state = State::ReqHttpHTTP;
                     },
                     State::ReqHttpHTTP => {
-                        strict_check!(self, ch != b'/', index);
+                        strict_check!(self, protocol, ch != b'/', index);
                         self.state = State::ReqFirstHttpMajor;
                     },
                     // first digit of major HTTP version
                     State::ReqFirstHttpMajor => {
-                        if ch < b'1' || ch > b'9' {
+                        if !(b'1'..=b'9').contains(&ch) {
                             self.errno = Option::Some(HttpErrno::InvalidVersion);
                             return index;
                         }
@@ -791,6 +1096,7 @@ impl HttpParser {
                         if ch == CR {
                             self.state = State::ReqLineAlmostDone;
                         } else if ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = State::HeaderFieldStart;
                         } else if is_num(ch) {
                             self.http_version.minor *= 10;
@@ -807,11 +1113,7 @@ impl HttpParser {
                     },
                     // end of request line
                     State::ReqLineAlmostDone => {
-                        if ch != LF {
-                            self.errno = Option::Some(HttpErrno::LFExpected);
-                            return index;
-                        }
-
+                        lf_check!(self, ch, index);
                         self.state = State::HeaderFieldStart;
                     },
                     State::HeaderFieldStart => {
@@ -821,30 +1123,44 @@ impl HttpParser {
                             // they might be just sending \n instead of \r\n,
                             // so this would be the second \n to denote
                             // the end of headers
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = State::HeadersAlmostDone;
                             retry = true;
                         } else {
-                            if !is_header_char(self.strict, ch) {
+                            if !is_header_char(self.settings.leniency.header_name_spaces, ch) {
                                 self.errno = Option::Some(HttpErrno::InvalidHeaderToken);
                                 return index;
                             }
 
+                            self.nheaders += 1;
+                            if self.nheaders > self.settings.max_headers {
+                                self.errno = Option::Some(HttpErrno::HeaderCountOverflow);
+                                return index;
+                            }
+
                             mark!(header_field_mark, index);
 
                             self.index = 0;
+                            self.header_value_len = 0;
                             self.state = State::HeaderField;
 
-                            match ch {
-                                b'c' | b'C' => self.header_state = HeaderState::C,
-                                b'p' | b'P' => self.header_state = HeaderState::MatchingProxyConnection,
-                                b't' | b'T' => self.header_state = HeaderState::MatchingTransferEncoding,
-                                b'u' | b'U' => self.header_state = HeaderState::MatchingUpgrade,
-                                _    => self.header_state = HeaderState::General,
+                            // Trailer fields never affect the connection or the framing
+                            if (self.flags & Flags::Trailing.as_u16()) != 0 {
+                                self.header_state = HeaderState::General;
+                            } else {
+                                match ch {
+                                    b'c' | b'C' => self.header_state = HeaderState::C,
+                                    b'p' | b'P' => self.header_state = HeaderState::MatchingProxyConnection,
+                                    b't' | b'T' => self.header_state = HeaderState::MatchingTransferEncoding,
+                                    b'u' | b'U' => self.header_state = HeaderState::MatchingUpgrade,
+                                    b'e' | b'E' => self.header_state = HeaderState::MatchingExpect,
+                                    _    => self.header_state = HeaderState::General,
+                                }
                             }
                         }
                     },
                     State::HeaderField => {
-                        if is_header_char(self.strict, ch) {
+                        if is_header_char(self.settings.leniency.header_name_spaces, ch) {
                             let c : u8 = lower(ch);
                             match self.header_state {
                                 HeaderState::General => (),
@@ -859,12 +1175,12 @@ impl HttpParser {
                                 HeaderState::CO => {
                                     self.index += 1;
                                     self.header_state = if c == b'n' {
-                                        HeaderState::CON
+                                        HeaderState::Con
                                     } else {
                                         HeaderState::General
                                     };
                                 },
-                                HeaderState::CON => {
+                                HeaderState::Con => {
                                     self.index += 1;
                                     match c {
                                         b'n' => self.header_state = HeaderState::MatchingConnection,
@@ -875,8 +1191,7 @@ impl HttpParser {
                                 // connection
                                 HeaderState::MatchingConnection => {
                                     self.index += 1;
-                                    if self.index >= CONNECTION.len() ||
-                                        c != (CONNECTION[self.index ..].bytes().next().unwrap()) {
+                                    if CONNECTION.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
                                     } else if self.index == CONNECTION.len()-1 {
                                         self.header_state = HeaderState::Connection;
@@ -885,8 +1200,7 @@ impl HttpParser {
                                 // proxy-connection
                                 HeaderState::MatchingProxyConnection => {
                                     self.index += 1;
-                                    if self.index >= PROXY_CONNECTION.len() ||
-                                        c != (PROXY_CONNECTION[self.index ..].bytes().next().unwrap()) {
+                                    if PROXY_CONNECTION.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
                                     } else if self.index == PROXY_CONNECTION.len()-1 {
                                         self.header_state = HeaderState::Connection;
@@ -895,8 +1209,7 @@ impl HttpParser {
                                 // content-length
                                 HeaderState::MatchingContentLength => {
                                     self.index += 1;
-                                    if self.index >= CONTENT_LENGTH.len() ||
-                                        c != (CONTENT_LENGTH[self.index ..].bytes().next().unwrap()) {
+                                    if CONTENT_LENGTH.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
                                     } else if self.index == CONTENT_LENGTH.len()-1 {
                                         self.header_state = HeaderState::ContentLength;
@@ -905,8 +1218,7 @@ impl HttpParser {
                                 // transfer-encoding
                                 HeaderState::MatchingTransferEncoding => {
                                     self.index += 1;
-                                    if self.index >= TRANSFER_ENCODING.len() ||
-                                        c != (TRANSFER_ENCODING[self.index ..].bytes().next().unwrap()) {
+                                    if TRANSFER_ENCODING.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
                                     } else if self.index == TRANSFER_ENCODING.len()-1 {
                                         self.header_state = HeaderState::TransferEncoding;
@@ -915,17 +1227,26 @@ impl HttpParser {
                                 // upgrade
                                 HeaderState::MatchingUpgrade => {
                                     self.index += 1;
-                                    if self.index >= UPGRADE.len() ||
-                                        c != (UPGRADE[self.index ..].bytes().next().unwrap()) {
+                                    if UPGRADE.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
                                     } else if self.index == UPGRADE.len()-1 {
                                         self.header_state = HeaderState::Upgrade;
                                     }
                                 },
+                                // expect
+                                HeaderState::MatchingExpect => {
+                                    self.index += 1;
+                                    if EXPECT.as_bytes().get(self.index) != Some(&c) {
+                                        self.header_state = HeaderState::General;
+                                    } else if self.index == EXPECT.len()-1 {
+                                        self.header_state = HeaderState::Expect;
+                                    }
+                                },
                                 HeaderState::Connection |
                                 HeaderState::ContentLength |
                                 HeaderState::TransferEncoding |
-                                HeaderState::Upgrade => {
+                                HeaderState::Upgrade |
+                                HeaderState::Expect => {
                                     if ch != b' ' {
                                         self.header_state = HeaderState::General;
                                     }
@@ -936,10 +1257,8 @@ impl HttpParser {
                             }
                         } else if ch == b':' {
                             self.state = State::HeaderValueDiscardWs;
-                            if header_field_mark.is_some() {
-                                callback!(self,
-                                    cb.on_header_field(self, &data[header_field_mark.unwrap() .. index]),
-                                    HttpErrno::CBHeaderField, index+1);
+                            if let Some(mark) = header_field_mark {
+                                header_field_callback!(self, cb, &data[mark .. index], index+1);
                                 header_field_mark = Option::None;
                             }
                         } else {
@@ -954,81 +1273,181 @@ impl HttpParser {
                         } else if ch == CR {
                             self.state = State::HeaderValueDiscardWsAlmostDone;
                         } else if ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = State::HeaderValueDiscardLws;
                         }
                     },
                     State::HeaderValueDiscardWs |
                     State::HeaderValueStart => {
+                        header_value_len_check!(self, index);
                         mark!(header_value_mark, index);
 
+                        // HeaderValueStart continues a folded value
+                        let folded = self.state == State::HeaderValueStart;
                         self.state = State::HeaderValue;
                         self.index = 0;
 
                         let c: u8 = lower(ch);
 
+                        if self.header_state.is_connection_state() {
+                            if folded {
+                                self.connection.push(b' ');
+                            } else if !self.connection.is_empty() {
+                                // Multiple Connection headers form a single list
+                                self.connection.extend_from_slice(b", ");
+                            }
+                            self.connection.push(ch);
+                        }
+
                         match self.header_state {
                             HeaderState::Upgrade => {
-                                self.flags |= Flags::Upgrade.as_u8();
-                                self.header_state = HeaderState::General;
+                                self.flags |= Flags::Upgrade.as_u16();
+                                if !folded && !self.upgrade_protocol.is_empty() {
+                                    // Multiple Upgrade headers form a single list
+                                    self.upgrade_protocol.extend_from_slice(b", ");
+                                }
+                                self.upgrade_protocol.push(ch);
                             },
                             HeaderState::TransferEncoding => {
-                                // looking for 'Transfer-Encoding: chunked
-                                if c == b'c' {
-                                    self.header_state = HeaderState::MatchingTransferEncodingChunked;
-                                } else {
-                                    self.header_state = HeaderState::General;
+                                self.flags |= Flags::TransferEncoding.as_u16();
+                                if folded {
+                                    self.transfer_encoding.push(b' ');
+                                } else if !self.transfer_encoding.is_empty() {
+                                    // Multiple Transfer-Encoding headers form a single list
+                                    self.transfer_encoding.extend_from_slice(b", ");
                                 }
+                                self.transfer_encoding.push(ch);
                             },
-                            HeaderState::ContentLength => {
+                            HeaderState::ContentLength |
+                            HeaderState::ContentLengthWs => {
+                                if !self.settings.leniency.ambiguous_framing {
+                                    // A folded value puts whitespace between the digits
+                                    if folded {
+                                        self.errno = Option::Some(HttpErrno::InvalidContentLength);
+                                        return index;
+                                    }
+                                    if (self.flags & Flags::ContentLength.as_u16()) != 0 {
+                                        self.errno = Option::Some(HttpErrno::DuplicateContentLength);
+                                        return index;
+                                    }
+                                }
+                                self.flags |= Flags::ContentLength.as_u16();
+
                                 if !is_num(ch) {
                                     self.errno = Option::Some(HttpErrno::InvalidContentLength);
                                     return index;
                                 }
 
+                                self.header_state = HeaderState::ContentLength;
                                 self.content_length = (ch - b'0') as u64;
                             },
                             HeaderState::Connection => {
-                                // looking for 'Connection: keep-alive
-                                if c == b'k' {
-                                    self.header_state = HeaderState::MatchingConnectionKeepAlive;
-                                // looking for 'Connection: close
-                                } else if c == b'c' {
-                                    self.header_state = HeaderState::MatchingConnectionClose;
+                                self.header_state = connection_token_start(ch);
+                            },
+                            HeaderState::Expect => {
+                                // looking for 'Expect: 100-continue
+                                if c == b'1' {
+                                    self.header_state = HeaderState::MatchingExpectContinue;
                                 } else {
                                     self.header_state = HeaderState::General;
                                 }
                             },
+                            // A folded Connection value continues the token list
+                            HeaderState::MatchingConnectionTokenStart |
+                            HeaderState::MatchingConnectionToken |
+                            HeaderState::ConnectionKeepAlive |
+                            HeaderState::ConnectionClose |
+                            HeaderState::ConnectionUpgrade if folded => (),
+                            HeaderState::MatchingConnectionKeepAlive |
+                            HeaderState::MatchingConnectionClose |
+                            HeaderState::MatchingConnectionUpgrade if folded => {
+                                self.header_state = HeaderState::MatchingConnectionToken;
+                            },
                             _ => self.header_state = HeaderState::General,
                         }
+
+                        if folded && self.settings.obs_fold == ObsFold::Unfold {
+                            // Replace the fold with a single space, passed on if the value goes on
+                            header_value_mark = Option::None;
+                            self.state = State::HeaderValueUnfold;
+                            self.unfold_ows.clear();
+                            self.unfold_ows.push(b' ');
+                        }
+                    },
+                    State::HeaderValueUnfold => {
+                        if ch == CR {
+                            self.state = State::HeaderAlmostDone;
+                            self.unfold_ows.clear();
+                        } else if ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
+                            self.state = State::HeaderAlmostDone;
+                            self.unfold_ows.clear();
+                            retry = true;
+                        } else if ch != b' ' && ch != b'\t' {
+                            self.state = State::HeaderValue;
+                            retry = true;
+                        }
                     },
                     State::HeaderValue => {
                         if ch == CR {
                             self.state = State::HeaderAlmostDone;
-                            if header_value_mark.is_some() {
-                                callback!(self,
-                                    cb.on_header_value(self, &data[header_value_mark.unwrap() .. index]),
-                                    HttpErrno::CBHeaderValue, index+1);
+                            // Whitespace at the end of an unfolded line is dropped
+                            self.unfold_ows.clear();
+                            if let Some(mark) = header_value_mark {
+                                header_value_callback!(self, cb, &data[mark .. index], index+1);
                                 header_value_mark = Option::None;
                             }
                         } else if ch == LF {
+                            strict_check!(self, bare_lf, ch == LF, index);
                             self.state = State::HeaderAlmostDone;
-                            if header_value_mark.is_some() {
-                                callback!(self,
-                                    cb.on_header_value(self, &data[header_value_mark.unwrap() .. index]),
-                                    HttpErrno::CBHeaderValue, index);
+                            self.unfold_ows.clear();
+                            if let Some(mark) = header_value_mark {
+                                header_value_callback!(self, cb, &data[mark .. index], index);
                                 header_value_mark = Option::None;
                             }
                             retry = true;
                         } else {
+                            if self.settings.obs_fold == ObsFold::Unfold {
+                                // Whitespace is held back until the value goes on, so that the
+                                // whitespace around a fold becomes a single space
+                                if ch == b' ' || ch == b'\t' {
+                                    if let Some(mark) = header_value_mark {
+                                        header_value_mark = Option::None;
+                                        if mark < index {
+                                            header_value_callback!(self, cb, &data[mark .. index], index);
+                                        }
+                                    }
+                                    self.unfold_ows.push(ch);
+                                } else if header_value_mark.is_none() {
+                                    if !self.unfold_ows.is_empty() {
+                                        let ows = mem::take(&mut self.unfold_ows);
+                                        header_value_callback!(self, cb, &ows, index);
+                                    }
+                                    mark!(header_value_mark, index);
+                                }
+                            }
+
+                            header_value_len_check!(self, index);
                             let c: u8 = lower(ch);
 
+                            if self.header_state.is_connection_state() {
+                                self.connection.push(ch);
+                            }
+
                             match self.header_state {
                                 HeaderState::General => (),
-                                HeaderState::Connection | HeaderState::TransferEncoding => {
+                                HeaderState::Connection => {
                                     panic!("Shouldn't get here.");
                                 },
+                                HeaderState::Upgrade => self.upgrade_protocol.push(ch),
+                                HeaderState::TransferEncoding => self.transfer_encoding.push(ch),
                                 HeaderState::ContentLength => {
-                                    if ch != b' ' {
+                                    if ch == b' ' {
+                                        // Only whitespace may follow the digits
+                                        if !self.settings.leniency.ambiguous_framing {
+                                            self.header_state = HeaderState::ContentLengthWs;
+                                        }
+                                    } else {
                                         if !is_num(ch) {
                                             self.errno = Option::Some(HttpErrno::InvalidContentLength);
                                             return index;
@@ -1048,39 +1467,63 @@ impl HttpParser {
                                         self.content_length = t;
                                     }
                                 },
-                                // Transfer-Encoding: chunked
-                                HeaderState::MatchingTransferEncodingChunked => {
-                                    self.index += 1;
-                                    if self.index >= CHUNKED.len() ||
-                                        c != (CHUNKED[self.index ..].bytes().next().unwrap()) {
-                                        self.header_state = HeaderState::General;
-                                    } else if self.index == CHUNKED.len()-1 {
-                                        self.header_state = HeaderState::TransferEncodingChunked;
+                                HeaderState::ContentLengthWs => {
+                                    if ch != b' ' {
+                                        self.errno = Option::Some(HttpErrno::InvalidContentLength);
+                                        return index;
+                                    }
+                                },
+                                // looking for the next token of 'Connection: a, b, c'
+                                HeaderState::MatchingConnectionTokenStart => {
+                                    self.index = 0;
+                                    self.header_state = connection_token_start(ch);
+                                },
+                                HeaderState::MatchingConnectionToken => {
+                                    if ch == b',' {
+                                        self.header_state = HeaderState::MatchingConnectionTokenStart;
                                     }
                                 },
                                 // looking for 'Connection: keep-alive
                                 HeaderState::MatchingConnectionKeepAlive => {
                                     self.index += 1;
-                                    if self.index >= KEEP_ALIVE.len() ||
-                                        c != (KEEP_ALIVE[self.index ..].bytes().next().unwrap()) {
-                                        self.header_state = HeaderState::General;
-                                    } else if self.index == KEEP_ALIVE.len()-1 {
-                                        self.header_state = HeaderState::ConnectionKeepAlive;
-                                    }
-                                }
+                                    self.header_state = match_connection_token(KEEP_ALIVE, self.index, c,
+                                        HeaderState::MatchingConnectionKeepAlive,
+                                        HeaderState::ConnectionKeepAlive);
+                                },
                                 // looking for 'Connection: close
                                 HeaderState::MatchingConnectionClose => {
                                     self.index += 1;
-                                    if self.index >= CLOSE.len() ||
-                                        c != (CLOSE[self.index ..].bytes().next().unwrap()) {
+                                    self.header_state = match_connection_token(CLOSE, self.index, c,
+                                        HeaderState::MatchingConnectionClose,
+                                        HeaderState::ConnectionClose);
+                                },
+                                // looking for 'Connection: upgrade
+                                HeaderState::MatchingConnectionUpgrade => {
+                                    self.index += 1;
+                                    self.header_state = match_connection_token(UPGRADE, self.index, c,
+                                        HeaderState::MatchingConnectionUpgrade,
+                                        HeaderState::ConnectionUpgrade);
+                                },
+                                HeaderState::ConnectionKeepAlive |
+                                HeaderState::ConnectionClose |
+                                HeaderState::ConnectionUpgrade => {
+                                    if ch == b',' {
+                                        self.set_connection_flag();
+                                        self.header_state = HeaderState::MatchingConnectionTokenStart;
+                                    } else if ch != b' ' && ch != b'\t' {
+                                        self.header_state = HeaderState::MatchingConnectionToken;
+                                    }
+                                },
+                                // looking for 'Expect: 100-continue
+                                HeaderState::MatchingExpectContinue => {
+                                    self.index += 1;
+                                    if CONTINUE.as_bytes().get(self.index) != Some(&c) {
                                         self.header_state = HeaderState::General;
-                                    } else if self.index == CLOSE.len()-1 {
-                                        self.header_state = HeaderState::ConnectionClose;
+                                    } else if self.index == CONTINUE.len()-1 {
+                                        self.header_state = HeaderState::ExpectContinue;
                                     }
                                 },
-                                HeaderState::TransferEncodingChunked |
-                                HeaderState::ConnectionKeepAlive |
-                                HeaderState::ConnectionClose => {
+                                HeaderState::ExpectContinue => {
                                     if ch != b' ' {
                                         self.header_state = HeaderState::General;
                                     }
@@ -1093,24 +1536,25 @@ impl HttpParser {
                         }
                     },
                     State::HeaderAlmostDone => {
-                        strict_check!(self, ch != LF, index);
+                        lf_check!(self, ch, index);
                         self.state = State::HeaderValueLws;
                     },
                     State::HeaderValueLws => {
                         if ch == b' ' || ch == b'\t' {
+                            if self.settings.obs_fold == ObsFold::Reject {
+                                self.errno = Option::Some(HttpErrno::ObsFold);
+                                return index;
+                            }
                             self.state = State::HeaderValueStart;
                             retry = true;
                         } else {
                             // finished the header
                             match self.header_state {
-                                HeaderState::ConnectionKeepAlive => {
-                                    self.flags |= Flags::ConnectionKeepAlive.as_u8();
-                                },
-                                HeaderState::ConnectionClose => {
-                                    self.flags |= Flags::ConnectionClose.as_u8();
-                                },
-                                HeaderState::TransferEncodingChunked => {
-                                    self.flags |= Flags::Chunked.as_u8();
+                                HeaderState::ConnectionKeepAlive |
+                                HeaderState::ConnectionClose |
+                                HeaderState::ConnectionUpgrade => self.set_connection_flag(),
+                                HeaderState::ExpectContinue => {
+                                    self.flags |= Flags::ExpectContinue.as_u16();
                                 },
                                 _ => (),
                             }
@@ -1120,7 +1564,7 @@ impl HttpParser {
                         }
                     },
                     State::HeaderValueDiscardWsAlmostDone => {
-                        strict_check!(self, ch != LF, index);
+                        lf_check!(self, ch, index);
                         self.state = State::HeaderValueDiscardLws;
                     },
                     State::HeaderValueDiscardLws => {
@@ -1128,38 +1572,74 @@ impl HttpParser {
                             self.state = State::HeaderValueDiscardWs;
                         } else {
                             // header value was empty
+                            if matches!(self.header_state, HeaderState::ContentLength) {
+                                if !self.settings.leniency.ambiguous_framing &&
+                                    (self.flags & Flags::ContentLength.as_u16()) != 0 {
+                                    self.errno = Option::Some(HttpErrno::DuplicateContentLength);
+                                } else {
+                                    // The body would be taken for the next message
+                                    self.errno = Option::Some(HttpErrno::InvalidContentLength);
+                                }
+                                return index;
+                            }
+
                             mark!(header_value_mark, index);
                             self.state = State::HeaderFieldStart;
-                            if header_value_mark.is_some() {
-                                callback!(self,
-                                    cb.on_header_value(self, &data[header_value_mark.unwrap() .. index]),
-                                    HttpErrno::CBHeaderValue, index);
+                            if let Some(mark) = header_value_mark {
+                                header_value_callback!(self, cb, &data[mark .. index], index);
                                 header_value_mark = Option::None;
                             }
                             retry = true;
                         }
                     },
                     State::HeadersAlmostDone => {
-                        strict_check!(self, ch != LF, index);
+                        lf_check!(self, ch, index);
 
-                        if (self.flags & Flags::Trailing.as_u8()) > 0 {
+                        if (self.flags & Flags::Trailing.as_u16()) > 0 {
                             // End of a chunked request
-                            self.new_message();
-                            callback!(self, cb.on_message_complete(self),
-                                      HttpErrno::CBMessageComplete, index+1);
+                            self.state = State::TrailersDone;
+                            callback!(self, cb.on_trailers_complete(self),
+                                      HttpErrno::CBTrailersComplete, index);
+                            retry = true;
                         } else {
+                            if self.final_coding_is_chunked() {
+                                self.flags |= Flags::Chunked.as_u16();
+                            }
+
+                            if let Some(errno) = self.framing_error() {
+                                self.errno = Option::Some(errno);
+                                return index;
+                            }
+
                             self.state = State::HeadersDone;
 
+                            if self.content_length != ULLONG_MAX {
+                                self.announced_content_length = Option::Some(self.content_length);
+
+                                // Transfer-Encoding overrides Content-Length, a response that
+                                // isn't chunked is read until the connection closes
+                                if self.status_code.is_some() &&
+                                    (self.flags & Flags::TransferEncoding.as_u16()) != 0 &&
+                                    (self.flags & Flags::Chunked.as_u16()) == 0 {
+                                    self.content_length = ULLONG_MAX;
+                                }
+                            }
+
                             // Set this here so that on_headers_complete()
                             // callbacks can see it
-                            self.upgrade = (self.flags & Flags::Upgrade.as_u8() != 0) ||
-                                self.method == Option::Some(HttpMethod::Connect);
+                            self.upgrade = self.is_upgrade();
+
+                            if self.expects_continue() {
+                                self.state = State::ExpectContinue;
+                            }
 
                             match cb.on_headers_complete(self) {
                                 Ok(ParseAction::None) => (),
-                                Ok(ParseAction::SkipBody) => self.flags |= Flags::SkipBody.as_u8(),
-                                _     => {
+                                Ok(ParseAction::SkipBody) => self.flags |= Flags::SkipBody.as_u16(),
+                                Ok(ParseAction::Pause) => self.errno = Option::Some(HttpErrno::Paused),
+                                Err(reason) => {
                                     self.errno = Option::Some(HttpErrno::CBHeadersComplete);
+                                    self.callback_error = Option::Some(reason);
                                     return index; // Error
                                 },
                             }
@@ -1170,8 +1650,31 @@ impl HttpParser {
                             retry = true;
                         }
                     },
+                    State::ExpectContinue => {
+                        self.state = State::HeadersDone;
+
+                        if (self.flags & Flags::SkipBody.as_u16()) == 0 {
+                            match cb.on_expect_continue(self) {
+                                Ok(ParseAction::None) => (),
+                                Ok(ParseAction::SkipBody) => {
+                                    self.flags |= Flags::SkipBody.as_u16() |
+                                        Flags::ExpectRejected.as_u16();
+                                },
+                                Ok(ParseAction::Pause) => self.errno = Option::Some(HttpErrno::Paused),
+                                Err(reason) => {
+                                    self.errno = Option::Some(HttpErrno::CBExpectContinue);
+                                    self.callback_error = Option::Some(reason);
+                                },
+                            }
+
+                            if self.errno.is_some() {
+                                return index;
+                            }
+                        }
+                        retry = true;
+                    },
                     State::HeadersDone => {
-                        strict_check!(self, ch != LF, index);
+                        strict_check!(self, bare_lf, ch != LF, index);
                         self.nread = 0;
 
                         // Exit, The rest of the connect is in a different protocol
@@ -1182,11 +1685,11 @@ impl HttpParser {
                             return index+1;
                         }
 
-                        if (self.flags & Flags::SkipBody.as_u8()) != 0 {
+                        if (self.flags & Flags::SkipBody.as_u16()) != 0 {
                             self.new_message();
                             callback!(self, cb.on_message_complete(self),
                                       HttpErrno::CBMessageComplete, index+1);
-                        } else if (self.flags & Flags::Chunked.as_u8()) != 0 {
+                        } else if (self.flags & Flags::Chunked.as_u16()) != 0 {
                             // chunked encoding - ignore Content-Length header
                             self.state = State::ChunkSizeStart;
                         } else {
@@ -1197,6 +1700,10 @@ impl HttpParser {
                                           HttpErrno::CBMessageComplete, index+1);
                             } else if self.content_length != ULLONG_MAX {
                                 // Content-Length header given and non-zero
+                                if self.content_length > self.settings.max_body_size {
+                                    self.errno = Option::Some(HttpErrno::BodyTooLarge);
+                                    return index;
+                                }
                                 self.state = State::BodyIdentity;
                             } else {
                                 if self.tp == HttpParserType::Request ||
@@ -1226,9 +1733,9 @@ impl HttpParser {
                         if self.content_length == 0 {
                             self.state = State::MessageDone;
 
-                            if body_mark.is_some() {
+                            if let Some(mark) = body_mark {
                                 callback!(self,
-                                    cb.on_body(self, &data[body_mark.unwrap() .. (index + 1)]),
+                                    cb.on_body(self, &data[mark .. (index + 1)]),
                                     HttpErrno::CBBody, index);
                                 body_mark = Option::None;
                             }
@@ -1237,6 +1744,12 @@ impl HttpParser {
                     },
                     // read until EOF
                     State::BodyIdentityEof => {
+                        self.body_len += (len - index) as u64;
+                        if self.body_len > self.settings.max_body_size {
+                            self.errno = Option::Some(HttpErrno::BodyTooLarge);
+                            return index;
+                        }
+
                         mark!(body_mark, index);
                         index = len - 1;
                     },
@@ -1247,32 +1760,32 @@ impl HttpParser {
                     },
                     State::ChunkSizeStart => {
                         assert!(self.nread == 1);
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
 
-                        let unhex_val = unhex_value(ch);
-                        if unhex_val.is_none() {
-                            self.errno = Option::Some(HttpErrno::InvalidChunkSize);
-                            return index;
-                        }
+                        let unhex_val = match unhex_value(ch) {
+                            Some(val) => val,
+                            None => {
+                                self.errno = Option::Some(HttpErrno::InvalidChunkSize);
+                                return index;
+                            },
+                        };
 
-                        self.content_length = unhex_val.unwrap() as u64;
+                        self.content_length = unhex_val as u64;
+                        self.chunk_extension_len = 0;
                         self.state = State::ChunkSize;
                     },
                     State::ChunkSize => {
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
 
                         if ch == CR {
                             self.state = State::ChunkSizeAlmostDone;
+                        } else if ch == LF {
+                            strict_check!(self, chunk_crlf, ch == LF, index);
+                            self.state = State::ChunkSizeAlmostDone;
+                            retry = true;
                         } else {
                             let unhex_val = unhex_value(ch);
-                            if unhex_val.is_none() {
-                                if ch == b';' || ch == b' ' {
-                                    self.state = State::ChunkParameters;
-                                } else {
-                                    self.errno = Option::Some(HttpErrno::InvalidChunkSize);
-                                    return index;
-                                }
-                            } else {
+                            if let Some(val) = unhex_val {
                                 // Overflow? Test against a conservative limit for simplicity
                                 if (ULLONG_MAX - 16)/16 < self.content_length {
                                     self.errno = Option::Some(HttpErrno::InvalidContentLength);
@@ -1281,36 +1794,180 @@ impl HttpParser {
 
                                 let mut t: u64 = self.content_length;
                                 t *= 16;
-                                t += unhex_val.unwrap() as u64;
+                                t += val as u64;
 
                                 self.content_length = t;
+                            } else if ch == b';' {
+                                self.state = State::ChunkExtensionStart;
+                            } else if ch == b' ' || ch == b'\t' {
+                                self.state = State::ChunkParameters;
+                            } else {
+                                self.errno = Option::Some(HttpErrno::InvalidChunkSize);
+                                return index;
                             }
                         }
                     },
                     State::ChunkParameters => {
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
-                        // just ignore this shit. TODO check for overflow
+                        // Whitespace after the chunk size or an extension
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
                         if ch == CR {
                             self.state = State::ChunkSizeAlmostDone;
+                        } else if ch == LF {
+                            strict_check!(self, chunk_crlf, ch == LF, index);
+                            self.state = State::ChunkSizeAlmostDone;
+                            retry = true;
+                        } else if ch == b';' {
+                            self.state = State::ChunkExtensionStart;
+                        } else if ch != b' ' && ch != b'\t' {
+                            self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                            return index;
+                        }
+                    },
+                    State::ChunkExtensionStart => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if is_normal_header_char(ch) {
+                            mark!(chunk_extension_name_mark, index);
+                            self.state = State::ChunkExtensionName;
+                        } else if ch != b' ' && ch != b'\t' {
+                            self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                            return index;
+                        }
+                    },
+                    State::ChunkExtensionName => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if !is_normal_header_char(ch) {
+                            strict_check!(self, chunk_crlf, ch == LF, index);
+                            self.state = match ch {
+                                b'=' => State::ChunkExtensionValueStart,
+                                b';' => State::ChunkExtensionStart,
+                                CR | LF => State::ChunkSizeAlmostDone,
+                                b' ' | b'\t' => State::ChunkExtensionNameDone,
+                                _ => {
+                                    self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                                    return index;
+                                },
+                            };
+
+                            // A bare LF is parsed again in ChunkSizeAlmostDone
+                            retry = ch == LF;
+                            if let Some(mark) = chunk_extension_name_mark {
+                                callback!(self,
+                                    cb.on_chunk_extension_name(self, &data[mark .. index]),
+                                    HttpErrno::CBChunkExtensionName,
+                                    if retry { index } else { index+1 });
+                                chunk_extension_name_mark = Option::None;
+                            }
+                        }
+                    },
+                    State::ChunkExtensionNameDone => {
+                        // Whitespace after the extension name
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        match ch {
+                            b'=' => self.state = State::ChunkExtensionValueStart,
+                            b';' => self.state = State::ChunkExtensionStart,
+                            CR => self.state = State::ChunkSizeAlmostDone,
+                            LF => {
+                                strict_check!(self, chunk_crlf, ch == LF, index);
+                                self.state = State::ChunkSizeAlmostDone;
+                                retry = true;
+                            },
+                            b' ' | b'\t' => (),
+                            _ => {
+                                self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                                return index;
+                            },
+                        }
+                    },
+                    State::ChunkExtensionValueStart => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if ch == b'"' {
+                            mark!(chunk_extension_value_mark, index);
+                            self.state = State::ChunkExtensionQuotedValue;
+                        } else if is_normal_header_char(ch) {
+                            mark!(chunk_extension_value_mark, index);
+                            self.state = State::ChunkExtensionValue;
+                        } else if ch != b' ' && ch != b'\t' {
+                            self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                            return index;
+                        }
+                    },
+                    State::ChunkExtensionValue => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if !is_normal_header_char(ch) {
+                            strict_check!(self, chunk_crlf, ch == LF, index);
+                            self.state = match ch {
+                                b';' => State::ChunkExtensionStart,
+                                CR | LF => State::ChunkSizeAlmostDone,
+                                b' ' | b'\t' => State::ChunkParameters,
+                                _ => {
+                                    self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                                    return index;
+                                },
+                            };
+
+                            // A bare LF is parsed again in ChunkSizeAlmostDone
+                            retry = ch == LF;
+                            if let Some(mark) = chunk_extension_value_mark {
+                                callback!(self,
+                                    cb.on_chunk_extension_value(self, &data[mark .. index]),
+                                    HttpErrno::CBChunkExtensionValue,
+                                    if retry { index } else { index+1 });
+                                chunk_extension_value_mark = Option::None;
+                            }
+                        }
+                    },
+                    State::ChunkExtensionQuotedValue => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if ch == b'"' {
+                            self.state = State::ChunkParameters;
+
+                            if let Some(mark) = chunk_extension_value_mark {
+                                callback!(self,
+                                    cb.on_chunk_extension_value(self, &data[mark .. index+1]),
+                                    HttpErrno::CBChunkExtensionValue, index+1);
+                                chunk_extension_value_mark = Option::None;
+                            }
+                        } else if ch == b'\\' {
+                            self.state = State::ChunkExtensionQuotedValueEscape;
+                        } else if ch == CR || ch == LF || (ch < b' ' && ch != b'\t') || ch == 127 {
+                            self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                            return index;
+                        }
+                    },
+                    State::ChunkExtensionQuotedValueEscape => {
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        if ch == CR || ch == LF || (ch < b' ' && ch != b'\t') || ch == 127 {
+                            self.errno = Option::Some(HttpErrno::InvalidChunkExtension);
+                            return index;
                         }
+                        self.state = State::ChunkExtensionQuotedValue;
                     },
                     State::ChunkSizeAlmostDone => {
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
-                        strict_check!(self, ch != LF, index);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        lf_check!(self, ch, index);
 
                         self.nread = 0;
 
+                        // Test the total of chunk sizes before any data of the chunk is read
+                        if self.settings.max_body_size - self.body_len < self.content_length {
+                            self.errno = Option::Some(HttpErrno::BodyTooLarge);
+                            return index;
+                        }
+                        self.body_len += self.content_length;
+
                         if self.content_length == 0 {
-                            self.flags |= Flags::Trailing.as_u8();
+                            self.flags |= Flags::Trailing.as_u16();
                             self.state = State::HeaderFieldStart;
                         } else {
                             self.state = State::ChunkData;
                         }
+                        callback!(self, cb.on_chunk_header(self, self.content_length),
+                                  HttpErrno::CBChunkHeader, index+1);
                     },
                     State::ChunkData => {
                         let to_read: usize = cmp::min(self.content_length,
                                                          (len - index) as u64) as usize;
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
                         assert!(self.content_length != 0 &&
                                 self.content_length != ULLONG_MAX);
 
@@ -1323,23 +1980,40 @@ impl HttpParser {
                         }
                     },
                     State::ChunkDataAlmostDone => {
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
                         assert!(self.content_length == 0);
-                        strict_check!(self, ch != CR, index);
+                        if ch == LF {
+                            strict_check!(self, chunk_crlf, ch == LF, index);
+                        } else if ch != CR {
+                            // The chunk is longer than its size
+                            self.errno = Option::Some(HttpErrno::InvalidChunkSize);
+                            return index;
+                        }
                         self.state = State::ChunkDataDone;
 
-                        if body_mark.is_some() {
+                        // A bare LF is parsed again in ChunkDataDone
+                        retry = ch == LF;
+                        if let Some(mark) = body_mark {
                             callback!(self,
-                                cb.on_body(self, &data[body_mark.unwrap() .. index]),
-                                HttpErrno::CBBody, index+1);
+                                cb.on_body(self, &data[mark .. index]),
+                                HttpErrno::CBBody, if retry { index } else { index+1 });
                             body_mark = Option::None;
                         }
                     },
                     State::ChunkDataDone => {
-                        assert!(self.flags & Flags::Chunked.as_u8() != 0);
-                        strict_check!(self, ch != LF, index);
+                        assert!(self.flags & Flags::Chunked.as_u16() != 0);
+                        lf_check!(self, ch, index);
                         self.nread = 0;
                         self.state = State::ChunkSizeStart;
+                        callback!(self, cb.on_chunk_complete(self),
+                                  HttpErrno::CBChunkComplete, index+1);
+                    },
+                    State::TrailersDone => {
+                        // The last chunk is complete once its trailers are parsed
+                        self.state = State::MessageDone;
+                        callback!(self, cb.on_chunk_complete(self),
+                                  HttpErrno::CBChunkComplete, index);
+                        retry = true;
                     }
                 }
 
@@ -1358,42 +2032,87 @@ impl HttpParser {
                 (if header_value_mark.is_some() { 1 } else { 0 }) +
                 (if url_mark.is_some() { 1 } else { 0 }) +
                 (if body_mark.is_some() { 1 } else { 0 }) +
-                (if status_mark.is_some() { 1 } else { 0 }) <= 1);
+                (if status_mark.is_some() { 1 } else { 0 }) +
+                (if chunk_extension_name_mark.is_some() { 1 } else { 0 }) +
+                (if chunk_extension_value_mark.is_some() { 1 } else { 0 }) <= 1);
 
-        if header_field_mark.is_some() {
-            callback!(self,
-                cb.on_header_field(self, &data[header_field_mark.unwrap() .. index]),
-                HttpErrno::CBHeaderField, index);
+        if let Some(mark) = header_field_mark {
+            header_field_callback!(self, cb, &data[mark .. index], index);
         }
-        if header_value_mark.is_some() {
-            callback!(self,
-                cb.on_header_value(self, &data[header_value_mark.unwrap() .. index]),
-                HttpErrno::CBHeaderValue, index);
+        if let Some(mark) = header_value_mark {
+            header_value_callback!(self, cb, &data[mark .. index], index);
         }
-        if url_mark.is_some() {
+        if let Some(mark) = url_mark {
             callback!(self,
-                cb.on_url(self, &data[url_mark.unwrap() .. index]),
+                cb.on_url(self, &data[mark .. index]),
                 HttpErrno::CBUrl, index);
         }
-        if body_mark.is_some() {
+        if let Some(mark) = body_mark {
             callback!(self,
-                cb.on_body(self, &data[body_mark.unwrap() .. index]),
+                cb.on_body(self, &data[mark .. index]),
                 HttpErrno::CBBody, index);
         }
-        if status_mark.is_some() {
+        if let Some(mark) = status_mark {
             callback!(self,
-                cb.on_status(self, &data[status_mark.unwrap() .. index]),
+                cb.on_status(self, &data[mark .. index]),
                 HttpErrno::CBStatus, index);
         }
+        if let Some(mark) = chunk_extension_name_mark {
+            callback!(self,
+                cb.on_chunk_extension_name(self, &data[mark .. index]),
+                HttpErrno::CBChunkExtensionName, index);
+        }
+        if let Some(mark) = chunk_extension_value_mark {
+            callback!(self,
+                cb.on_chunk_extension_value(self, &data[mark .. index]),
+                HttpErrno::CBChunkExtensionValue, index);
+        }
         len
     }
 
+    /// Returns the position and cause of the error that stopped parsing, if any.
+    ///
+    /// Pausing isn't an error, so it is `None` while the parser is paused.
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+
+    /// Returns the error returned by the callback that stopped parsing, if any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// struct Callback;
+    ///
+    /// impl HttpParserCallback for Callback {
+    ///     fn on_url(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+    ///         Err("unknown accessory".to_string())
+    ///     }
+    /// }
+    ///
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// parser.execute(&mut Callback, b"GET /accessories HTTP/1.1\r\n");
+    ///
+    /// assert_eq!(parser.callback_error(), Some("unknown accessory"));
+    /// ```
+    pub fn callback_error(&self) -> Option<&str> {
+        self.error.as_ref().and_then(|e| e.reason.as_ref()).map(|reason| reason.as_str())
+    }
+
     /// Returns true if the HTTP body is final.
     pub fn http_body_is_final(&self) -> bool {
-        self.state == State::MessageDone
+        self.state == State::TrailersDone || self.state == State::MessageDone
     }
 
-    /// Pauses the parser.
+    /// Pauses or unpauses the parser.
+    ///
+    /// A callback may call it, or return `ParseAction::Pause`, to stop `execute` right after
+    /// the current event.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parser has failed with an error other than `HttpErrno::Paused`.
     pub fn pause(&mut self, pause: bool) {
         if self.errno.is_none() || self.errno == Option::Some(HttpErrno::Paused) {
             self.errno = if pause {
@@ -1406,16 +2125,64 @@ impl HttpParser {
         }
     }
 
+    /// Resumes a paused parser.
+    ///
+    /// The next `execute` should be given the data starting at the offset returned by the
+    /// `execute` call that paused.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parser has failed with an error other than `HttpErrno::Paused`.
+    pub fn resume(&mut self) {
+        self.pause(false);
+    }
+
+    /// Rejects the `Expect: 100-continue` of a request the parser paused on, as if
+    /// `on_expect_continue` had returned `ParseAction::SkipBody`.
+    ///
+    /// Call it before `resume` once the server decides not to read the body: the message is
+    /// complete without a body and the connection isn't kept alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parser isn't paused.
+    pub fn reject_expect(&mut self) {
+        if !self.is_paused() {
+            panic!("Attempting to reject an expectation of a parser that isn't paused");
+        }
+
+        if self.expects_continue() {
+            self.flags |= Flags::SkipBody.as_u16() | Flags::ExpectRejected.as_u16();
+        }
+    }
+
+    /// Returns true if the parser is paused.
+    pub fn is_paused(&self) -> bool {
+        self.errno == Option::Some(HttpErrno::Paused)
+    }
+
     /// Returns true if it needs to keep alive.
+    ///
+    /// HAP `EVENT/1.0` notifications are sent on the connection of the HAP session, which stays
+    /// open regardless of their version and Connection header.
     pub fn http_should_keep_alive(&self) -> bool {
+        // The client may send the body of a rejected request anyway
+        if (self.flags & Flags::ExpectRejected.as_u16()) != 0 {
+            return false
+        }
+
+        if self.is_event() {
+            return true
+        }
+
         if self.http_version.major > 0 && self.http_version.minor > 0 {
             // HTTP/1.1
-            if (self.flags & Flags::ConnectionClose.as_u8()) != 0 {
+            if (self.flags & Flags::ConnectionClose.as_u16()) != 0 {
                 return false
             }
         } else {
             // HTTP/1.0 or earlier
-            if (self.flags & Flags::ConnectionKeepAlive.as_u8()) == 0 {
+            if (self.flags & Flags::ConnectionKeepAlive.as_u16()) == 0 {
                 return false
             }
         }
@@ -1423,110 +2190,374 @@ impl HttpParser {
         !self.http_message_needs_eof()
     }
 
-    // Our URL parser
-    fn parse_url_char(&self, s: State, ch: u8) -> State {
+    /// Returns the form of the request-target, or `None` for a response.
+    ///
+    /// It is known once `on_url` is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// parser.execute(&mut Callback, b"GET http://example.com/ HTTP/1.1\r\n\r\n");
+    ///
+    /// assert_eq!(parser.request_target(), Some(RequestTarget::Absolute));
+    /// ```
+    pub fn request_target(&self) -> Option<RequestTarget> {
+        self.request_target
+    }
+
+    /// Returns true if the message body is chunked.
+    ///
+    /// It is known once `on_headers_complete` is called.
+    pub fn is_chunked(&self) -> bool {
+        (self.flags & Flags::Chunked.as_u16()) != 0
+    }
+
+    /// Returns the value of the Content-Length header of the message, if any.
+    ///
+    /// It is known once `on_headers_complete` is called. A chunked message may announce a
+    /// Content-Length too, which is then ignored.
+    pub fn content_length(&self) -> Option<u64> {
+        self.announced_content_length
+    }
+
+    /// Returns the number of body bytes that are still to be parsed.
+    ///
+    /// It is `None` unless the body length is given by the Content-Length header, i.e. for
+    /// chunked bodies, for bodies read until EOF and before `on_headers_complete` is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// struct Callback;
+    ///
+    /// impl HttpParserCallback for Callback {
+    ///     fn on_body(&mut self, parser: &mut HttpParser, data: &[u8]) -> CallbackResult {
+    ///         assert_eq!(parser.remaining_body(), Some(0));
+    ///         Ok(ParseAction::None)
+    ///     }
+    /// }
+    ///
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// parser.execute(&mut Callback, b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nok");
+    /// ```
+    pub fn remaining_body(&self) -> Option<u64> {
+        if self.is_chunked() || self.announced_content_length.is_none() ||
+            self.content_length == ULLONG_MAX {
+            Option::None
+        } else {
+            Option::Some(self.content_length)
+        }
+    }
+
+    /// Returns true if the message has a `Connection: close` header.
+    pub fn has_connection_close(&self) -> bool {
+        (self.flags & Flags::ConnectionClose.as_u16()) != 0
+    }
+
+    /// Returns true if the message has a `Connection: keep-alive` header.
+    pub fn has_connection_keep_alive(&self) -> bool {
+        (self.flags & Flags::ConnectionKeepAlive.as_u16()) != 0
+    }
+
+    /// Returns true if the message has an Upgrade header.
+    ///
+    /// Whether the connection is actually upgraded is given by `upgrade`.
+    pub fn has_upgrade_header(&self) -> bool {
+        (self.flags & Flags::Upgrade.as_u16()) != 0
+    }
+
+    /// Returns true if the request has an `Expect: 100-continue` header.
+    pub fn has_expect_continue(&self) -> bool {
+        (self.flags & Flags::ExpectContinue.as_u16()) != 0
+    }
+
+    /// Returns true if the message has a `Connection: upgrade` header.
+    pub fn has_connection_upgrade(&self) -> bool {
+        (self.flags & Flags::ConnectionUpgrade.as_u16()) != 0
+    }
 
-        if ch == b' ' || ch == b'\r' || ch == b'\n' || (self.strict && (ch == b'\t' || ch == b'\x0C')) { // '\x0C' = '\f'
-            return State::Dead;
+    /// Returns the value of the Upgrade header, if any and if it is valid UTF-8.
+    ///
+    /// In a `101 Switching Protocols` response it is the protocol the connection switches to,
+    /// in a request the list of protocols offered by the client.
+    pub fn upgrade_protocol(&self) -> Option<&str> {
+        if self.upgrade_protocol.is_empty() {
+            return Option::None;
         }
+        str::from_utf8(&self.upgrade_protocol).ok().map(|protocol| protocol.trim_end())
+    }
 
-        match s {
-            State::ReqSpacesBeforeUrl => {
-                // Proxied requests are followed by scheme of an absolute URI (alpha).
-                // All methods except CONNECT are followed by '/' or '*'.
+    /// Returns the transfer codings of the Transfer-Encoding headers, in the order they were
+    /// applied.
+    ///
+    /// The body is chunked only if `chunked` is the final coding. The list is empty if there is
+    /// no Transfer-Encoding header or if its value isn't valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// let mut cb = Callback;
+    /// parser.execute(&mut cb, b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n");
+    ///
+    /// assert_eq!(parser.transfer_codings(), vec!["gzip", "chunked"]);
+    /// assert!(parser.is_chunked());
+    /// ```
+    pub fn transfer_codings(&self) -> Vec<&str> {
+        split_list(&self.transfer_encoding)
+    }
 
-                if ch == b'/' || ch == b'*' {
-                    return State::ReqPath;
-                }
+    /// Returns the tokens of the Connection headers, as sent by the peer.
+    ///
+    /// Besides `close`, `keep-alive` and `upgrade`, the tokens name the hop-by-hop headers of
+    /// the message, which a proxy removes before forwarding it. The list is empty if there is
+    /// no Connection header or if its value isn't valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let mut parser = HttpParser::new(HttpParserType::Request);
+    /// let mut cb = Callback;
+    /// parser.execute(&mut cb, b"GET / HTTP/1.1\r\nConnection: close, X-Trace\r\n\r\n");
+    ///
+    /// assert_eq!(parser.connection_tokens(), vec!["close", "X-Trace"]);
+    /// assert!(!parser.http_should_keep_alive());
+    /// ```
+    pub fn connection_tokens(&self) -> Vec<&str> {
+        split_list(&self.connection)
+    }
 
-                if is_alpha(ch) {
-                    return State::ReqSchema;
+    // Matches a byte of the method against the methods of HttpMethod
+    fn match_method_char(&mut self, ch: u8) -> bool {
+        let matcher = self.method.unwrap().as_str().as_bytes();
+        if ch == b' ' && self.index == matcher.len() {
+            self.state = State::ReqSpacesBeforeUrl;
+        } else if matcher.get(self.index) == Some(&ch) {
+            //noop
+        } else if self.method == Option::Some(HttpMethod::Connect) {
+            if self.index == 1 && ch == b'H' {
+                self.method = Option::Some(HttpMethod::Checkout);
+            } else if self.index == 2 && ch == b'P' {
+                self.method = Option::Some(HttpMethod::Copy);
+            } else {
+                return false
+            }
+        } else if self.method == Option::Some(HttpMethod::MKCol) {
+            if self.index == 1 && ch == b'O' {
+                self.method = Option::Some(HttpMethod::Move);
+            } else if self.index == 1 && ch == b'E' {
+                self.method = Option::Some(HttpMethod::Merge);
+            } else if self.index == 1 && ch == b'-' {
+                self.method = Option::Some(HttpMethod::MSearch);
+            } else if self.index == 2 && ch == b'A' {
+                self.method = Option::Some(HttpMethod::MKActivity);
+            } else if self.index == 3 && ch == b'A' {
+                self.method = Option::Some(HttpMethod::MKCalendar);
+            } else {
+                return false
+            }
+        } else if self.method == Option::Some(HttpMethod::Subscribe) {
+            if self.index == 1 && ch == b'E' {
+                self.method = Option::Some(HttpMethod::Search);
+            } else {
+                return false
+            }
+        } else if self.index == 1 && self.method == Option::Some(HttpMethod::Post) {
+           if ch == b'R' {
+               self.method = Option::Some(HttpMethod::PropFind); // or PropPatch
+           } else if ch == b'U' {
+               self.method = Option::Some(HttpMethod::Put); // or Purge
+           } else if ch == b'A' {
+               self.method = Option::Some(HttpMethod::Patch);
+           } else {
+               return false
+           }
+        } else if self.index == 2 {
+            if self.method == Option::Some(HttpMethod::Put) {
+                if ch == b'R' {
+                    self.method = Option::Some(HttpMethod::Purge);
+                } else {
+                    return false
                 }
-            },
-            State::ReqSchema => {
-                if is_alpha(ch) {
-                    return s;
+            } else if self.method == Option::Some(HttpMethod::Unlock) {
+                if ch == b'S' {
+                    self.method = Option::Some(HttpMethod::Unsubscribe);
+                } else {
+                    return false
                 }
+            } else {
+                return false
+            }
+        } else if self.index == 4 && self.method == Option::Some(HttpMethod::PropFind) && ch == b'P' {
+            self.method = Option::Some(HttpMethod::PropPatch);
+        } else {
+            return false
+        }
 
-                if ch == b':' {
-                    return State::ReqSchemaSlash;
-                }
+        true
+    }
+
+    // Matches a byte of the method against the extension methods of the settings
+    fn match_extension_method(&mut self, ch: u8) -> bool {
+        if ch != b' ' {
+            self.method = Option::None;
+            let token = &self.method_token;
+            return self.settings.methods.iter().any(|m| m.as_bytes().starts_with(token))
+        }
+
+        match self.settings.methods.iter().find(|m| m.as_bytes() == &self.method_token[..]) {
+            Some(m) => {
+                self.method = Option::Some(HttpMethod::Extension(m));
+                self.state = State::ReqSpacesBeforeUrl;
+                true
             },
-            State::ReqSchemaSlash => {
-                if ch == b'/' {
-                    return State::ReqSchemaSlashSlash;
-                }
+            None => false,
+        }
+    }
+
+    // Moves to the URL state of `ch` at `index` and validates the host and port of the URL.
+    // Fails with the error of the URL component `ch` is invalid in, keeping the state
+    fn next_url_state(&mut self, ch: u8, index: usize) -> Result<(), HttpErrno> {
+        let s = self.state;
+        let next = parse_url_char(self.settings.leniency.url_chars, s, ch);
+        match next {
+            State::Dead => return Err(url_errno(s)),
+            // What was parsed as a host is the userinfo
+            State::ReqServerWithAt => {
+                self.host_parser = HostParser::new(false);
+                self.host_error_offset = Option::None;
             },
-            State::ReqSchemaSlashSlash => {
-                if ch == b'/' {
-                    return State::ReqServerStart;
+            State::ReqServer => {
+                self.host_parser.parse_char(ch);
+                // The error is reported once the server part ends, a '@' may still make it
+                // the userinfo
+                if self.host_parser.state() == HostState::Dead && self.host_error_offset.is_none() {
+                    self.host_error_offset = Option::Some(self.offset + index as u64);
                 }
             },
-            State::ReqServerWithAt if ch == b'@' => return State::Dead,
-            State::ReqServerWithAt | State::ReqServerStart | State::ReqServer => {
-                if ch == b'/' {
-                    return State::ReqPath;
-                }
+            _ => self.end_url_server(s)?,
+        }
+        self.state = next;
+        Ok(())
+    }
 
-                if ch == b'?' {
-                    return State::ReqQueryStringStart;
-                }
+    // Checks the host and port of the URL when its server part ends in state `s`. An invalid
+    // host or port is reported at the byte the host parser failed at
+    fn end_url_server(&mut self, s: State) -> Result<(), HttpErrno> {
+        if is_server_state(s) {
+            if let Err(errno) = self.host_parser.finish() {
+                self.error_offset = self.host_error_offset;
+                return Err(errno);
+            }
+        }
+        Ok(())
+    }
 
-                if ch == b'@' {
-                    return State::ReqServerWithAt;
-                }
+    fn set_connection_flag(&mut self) {
+        match self.header_state {
+            HeaderState::ConnectionKeepAlive => self.flags |= Flags::ConnectionKeepAlive.as_u16(),
+            HeaderState::ConnectionClose => self.flags |= Flags::ConnectionClose.as_u16(),
+            HeaderState::ConnectionUpgrade => self.flags |= Flags::ConnectionUpgrade.as_u16(),
+            _ => (),
+        }
+    }
 
-                if is_userinfo_char(ch) || ch == b'[' || ch == b']' {
-                    return State::ReqServer;
-                }
-            },
-            State::ReqPath => {
-                if is_url_char(self.strict, ch) {
-                    return s;
-                }
+    // Does the message switch the connection to another protocol? See RFC 9110 section 7.8
+    // and 9.3.6
+    fn is_upgrade(&self) -> bool {
+        match self.status_code {
+            // A response answers the request with the method set by the caller
+            Some(status_code) => status_code == 101 ||
+                (status_code / 100 == 2 && self.method == Option::Some(HttpMethod::Connect)),
+            None => self.method == Option::Some(HttpMethod::Connect) ||
+                ((self.flags & Flags::Upgrade.as_u16()) != 0 &&
+                 (self.flags & Flags::ConnectionUpgrade.as_u16()) != 0),
+        }
+    }
 
-                match ch {
-                    b'?' => return State::ReqQueryStringStart,
-                    b'#' => return State::ReqFragmentStart,
-                    _    => (),
-                }
-            },
-            State::ReqQueryStringStart | State::ReqQueryString => {
-                if is_url_char(self.strict, ch) {
-                    return State::ReqQueryString;
-                }
+    // Does the client wait for 100 Continue before sending the body? See RFC 9110
+    // section 10.1.1
+    fn expects_continue(&self) -> bool {
+        if (self.flags & Flags::ExpectContinue.as_u16()) == 0 || self.status_code.is_some() ||
+            self.upgrade {
+            return false
+        }
 
-                match ch {
-                    b'?' => return State::ReqQueryString, // allow extra '?' in query string
-                    b'#' => return State::ReqFragmentStart,
-                    _    => (),
-                }
-            },
-            State::ReqFragmentStart => {
-                if is_url_char(self.strict, ch) {
-                    return State::ReqFragment;
-                }
+        // HTTP/1.0 clients don't wait
+        if self.http_version.major < 1 ||
+            (self.http_version.major == 1 && self.http_version.minor == 0) {
+            return false
+        }
 
-                match ch {
-                    b'?' => return State::ReqFragment,
-                    b'#' => return s,
-                    _    => (),
-                }
-            },
-            State::ReqFragment => {
-                if is_url_char(self.strict, ch) {
-                    return s;
-                }
+        (self.flags & Flags::Chunked.as_u16()) != 0 ||
+            (self.content_length != ULLONG_MAX && self.content_length > 0)
+    }
 
-                if ch == b'?' || ch == b'#' {
-                    return s;
-                }
-            },
-            _ => (),
+    // Is chunked the last coding of Transfer-Encoding? It may not be applied before any other
+    // coding, see RFC 9112 section 6.1
+    fn final_coding_is_chunked(&self) -> bool {
+        match self.transfer_codings().last() {
+            Some(coding) => coding.eq_ignore_ascii_case(CHUNKED),
+            None => false,
+        }
+    }
+
+    // Do the headers make the length of the body ambiguous? See RFC 9112 section 6.1
+    fn framing_error(&self) -> Option<HttpErrno> {
+        // An EVENT shares the connection with the responses, so it can't be read until EOF
+        if self.is_event() {
+            if (self.flags & Flags::TransferEncoding.as_u16()) != 0 {
+                return Option::Some(HttpErrno::InvalidTransferEncoding)
+            }
+            if (self.flags & Flags::ContentLength.as_u16()) == 0 {
+                return Option::Some(HttpErrno::MissingContentLength)
+            }
+            return Option::None
+        }
+
+        if self.settings.leniency.ambiguous_framing ||
+            (self.flags & Flags::TransferEncoding.as_u16()) == 0 {
+            return Option::None
+        }
+
+        // HTTP/1.0 has no transfer codings
+        if self.http_version.major < 1 ||
+            (self.http_version.major == 1 && self.http_version.minor == 0) {
+            return Option::Some(HttpErrno::InvalidTransferEncoding)
         }
 
-        // We should never fall out of the switch above unless there's an error
-        return State::Dead;
+        // chunked is applied at most once, see RFC 9112 section 6.1
+        if self.transfer_codings().iter().filter(|c| c.eq_ignore_ascii_case(CHUNKED)).count() > 1 {
+            return Option::Some(HttpErrno::InvalidTransferEncoding)
+        }
+
+        // A response may carry both, Transfer-Encoding overrides Content-Length there
+        if self.status_code.is_none() && (self.flags & Flags::ContentLength.as_u16()) != 0 {
+            return Option::Some(HttpErrno::UnexpectedContentLength)
+        }
+
+        // Only chunked delimits a request body, see RFC 9112 section 6.3
+        if self.status_code.is_none() && (self.flags & Flags::Chunked.as_u16()) == 0 {
+            return Option::Some(HttpErrno::InvalidTransferEncoding)
+        }
+
+        Option::None
+    }
+
+    // Is the message a HAP notification?
+    fn is_event(&self) -> bool {
+        self.status_code.is_some() && self.response_type == Option::Some(ResponseType::Event)
     }
 
     // Does the parser need to see an EOF to find the end of the message?
@@ -1540,11 +2571,11 @@ impl HttpParser {
         if status_code / 100 == 1 || // 1xx e.g. Continue
             status_code == 204 ||    // No Content
             status_code == 304 ||    // Not Modified
-            (self.flags & Flags::SkipBody.as_u8()) != 0 {// response to a HEAD request
+            (self.flags & Flags::SkipBody.as_u16()) != 0 {// response to a HEAD request
             return false
         }
 
-        if (self.flags & Flags::Chunked.as_u8() != 0) ||
+        if (self.flags & Flags::Chunked.as_u16() != 0) ||
             self.content_length != ULLONG_MAX {
             return false
         }
@@ -1552,16 +2583,30 @@ impl HttpParser {
         true
     }
 
+    fn start_message(&mut self, index: usize) {
+        self.line = 1;
+        self.line_offset = self.offset + index as u64;
+        self.next_line_offset = self.line_offset;
+        self.flags = 0;
+        self.content_length = ULLONG_MAX;
+        self.announced_content_length = Option::None;
+        self.upgrade_protocol.clear();
+        self.transfer_encoding.clear();
+        self.connection.clear();
+        self.method_token.clear();
+        self.status_code = Option::None;
+        self.request_target = Option::None;
+        self.nheaders = 0;
+        self.body_len = 0;
+    }
+
     fn new_message(&mut self) {
+        self.nread = 0;
         let new_state = if self.tp == HttpParserType::Request { State::StartReq } else { State::StartRes };
-        self.state = if self.strict {
-                        if self.http_should_keep_alive() {
-                            new_state
-                        } else {
-                            State::Dead
-                        }
-                    } else {
+        self.state = if self.settings.leniency.keep_alive || self.http_should_keep_alive() {
                         new_state
+                    } else {
+                        State::Dead
                     };
     }
 }
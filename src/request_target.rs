@@ -0,0 +1,12 @@
+/// Form of the request-target in the request line, see RFC 9112 section 3.2
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RequestTarget {
+    /// An absolute path and query, like `/accessories`
+    Origin,
+    /// An absolute URI, like `http://example.com/accessories`, sent to proxies
+    Absolute,
+    /// A host and port, like `example.com:443`, sent with CONNECT
+    Authority,
+    /// `*`, sent with OPTIONS to address the server instead of a resource
+    Asterisk,
+}
@@ -0,0 +1,250 @@
+//! Limits and options that tune the behavior of the parser.
+
+/// Default limit of the start line plus headers, in bytes.
+const HTTP_MAX_HEADER_SIZE: usize = 80*1024;
+
+/// Default limit of the extensions of a single chunk, in bytes.
+const HTTP_MAX_CHUNK_EXTENSION_SIZE: usize = 16*1024;
+
+/// `Leniency` selects the protocol violations that the parser tolerates.
+///
+/// Every leniency is disabled by default. A violation of a disabled leniency fails with
+/// `HttpErrno::Strict` unless noted otherwise.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let leniency = Leniency { bare_lf: true, ..Leniency::none() };
+/// let settings = ParserSettings::new().leniency(leniency);
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Leniency {
+    /// Accept a LF without the preceding CR as the end of the start line or of a header. A CR
+    /// without the following LF still fails with `HttpErrno::LFExpected`
+    pub bare_lf: bool,
+    /// Accept spaces in header names instead of failing with `HttpErrno::InvalidHeaderToken`
+    pub header_name_spaces: bool,
+    /// Accept a LF without the preceding CR as the end of a chunk size line or of chunk data
+    pub chunk_crlf: bool,
+    /// Keep parsing after a message that asks to close the connection, instead of failing
+    /// the next message with `HttpErrno::ClosedConnection`
+    pub keep_alive: bool,
+    /// Accept any protocol literal in place of `HTTP/` and `EVENT/`
+    pub protocol: bool,
+    /// Accept tab, form feed and non-ASCII bytes in the URL instead of failing with the error
+    /// of the URL component, like `HttpErrno::InvalidPath`
+    pub url_chars: bool,
+    /// Accept messages whose body length is ambiguous: a repeated `Content-Length` header,
+    /// whitespace between the digits of `Content-Length`, a request with both `Content-Length`
+    /// and `Transfer-Encoding`, a request whose final transfer coding isn't `chunked`, `chunked`
+    /// applied more than once, or `Transfer-Encoding` in an HTTP/1.0 message. These fail with
+    /// `HttpErrno::DuplicateContentLength`, `HttpErrno::InvalidContentLength`,
+    /// `HttpErrno::UnexpectedContentLength` and `HttpErrno::InvalidTransferEncoding` otherwise
+    pub ambiguous_framing: bool,
+    /// Accept the asterisk-form `*` with methods other than OPTIONS, and a CONNECT
+    /// request-target other than `host:port`
+    pub request_target: bool,
+}
+
+impl Leniency {
+    /// Returns a leniency that tolerates no violations.
+    pub fn none() -> Leniency {
+        Leniency::default()
+    }
+
+    /// Returns a leniency that tolerates every supported violation.
+    pub fn all() -> Leniency {
+        Leniency {
+            bare_lf: true,
+            header_name_spaces: true,
+            chunk_crlf: true,
+            keep_alive: true,
+            protocol: true,
+            url_chars: true,
+            ambiguous_framing: true,
+            request_target: true,
+        }
+    }
+}
+
+/// `ObsFold` selects how the parser handles obsolete line folding, a header value continued on
+/// the next line that starts with a space or a tab.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let settings = ParserSettings::new().obs_fold(ObsFold::Reject);
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ObsFold {
+    /// Pass the continuation line to `on_header_value` as a separate fragment that starts with
+    /// its leading whitespace
+    #[default]
+    Keep,
+    /// Fail with `HttpErrno::ObsFold`, as RFC 9112 section 5.2 recommends for servers
+    Reject,
+    /// Pass a single space to `on_header_value` in place of the line break and the whitespace
+    /// around it. Whitespace at the end of the value is dropped
+    Unfold,
+}
+
+/// `ParserSettings` holds the limits enforced by `HttpParser` while parsing.
+///
+/// The default settings keep the limits of the original http-parser: up to 80 KiB of start line
+/// and headers, with no separate bound on the number of headers or on the body size. The
+/// extensions of a chunk are limited to 16 KiB. No protocol violations are tolerated.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let settings = ParserSettings::new()
+///     .max_header_size(4 * 1024)
+///     .max_url_size(512)
+///     .max_headers(32);
+///
+/// let mut parser = HttpParser::with_settings(HttpParserType::Request, settings);
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParserSettings {
+    /// Maximum number of bytes in the start line and headers of a message
+    pub max_header_size: usize,
+    /// Maximum number of bytes in the request or status line
+    pub max_start_line_size: usize,
+    /// Maximum number of bytes in the request URL
+    pub max_url_size: usize,
+    /// Maximum number of header fields in a message
+    pub max_headers: usize,
+    /// Maximum number of bytes in a single header value
+    pub max_header_value_size: usize,
+    /// Maximum number of bytes in a message body
+    pub max_body_size: u64,
+    /// Maximum number of bytes in the extensions of a single chunk
+    pub max_chunk_extension_size: usize,
+    /// Protocol violations tolerated by the parser
+    pub leniency: Leniency,
+    /// Handling of obsolete line folding in header values
+    pub obs_fold: ObsFold,
+    /// Request methods accepted besides the methods of `HttpMethod`
+    pub methods: Vec<&'static str>,
+}
+
+impl ParserSettings {
+    /// Creates the default settings.
+    pub fn new() -> ParserSettings {
+        ParserSettings {
+            max_header_size: HTTP_MAX_HEADER_SIZE,
+            max_start_line_size: HTTP_MAX_HEADER_SIZE,
+            max_url_size: HTTP_MAX_HEADER_SIZE,
+            max_headers: usize::MAX,
+            max_header_value_size: HTTP_MAX_HEADER_SIZE,
+            max_body_size: u64::MAX,
+            max_chunk_extension_size: HTTP_MAX_CHUNK_EXTENSION_SIZE,
+            leniency: Leniency::none(),
+            obs_fold: ObsFold::Keep,
+            methods: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of bytes in the start line and headers.
+    ///
+    /// Exceeding it fails with `HttpErrno::HeaderOverflow`.
+    pub fn max_header_size(mut self, size: usize) -> ParserSettings {
+        self.max_header_size = size;
+        self
+    }
+
+    /// Sets the maximum number of bytes in the request or status line.
+    ///
+    /// Exceeding it fails with `HttpErrno::StartLineOverflow`.
+    pub fn max_start_line_size(mut self, size: usize) -> ParserSettings {
+        self.max_start_line_size = size;
+        self
+    }
+
+    /// Sets the maximum number of bytes in the request URL.
+    ///
+    /// Exceeding it fails with `HttpErrno::UrlOverflow`.
+    pub fn max_url_size(mut self, size: usize) -> ParserSettings {
+        self.max_url_size = size;
+        self
+    }
+
+    /// Sets the maximum number of header fields in a message.
+    ///
+    /// Exceeding it fails with `HttpErrno::HeaderCountOverflow`.
+    pub fn max_headers(mut self, count: usize) -> ParserSettings {
+        self.max_headers = count;
+        self
+    }
+
+    /// Sets the maximum number of bytes in a single header value.
+    ///
+    /// Exceeding it fails with `HttpErrno::HeaderValueOverflow`.
+    pub fn max_header_value_size(mut self, size: usize) -> ParserSettings {
+        self.max_header_value_size = size;
+        self
+    }
+
+    /// Sets the maximum number of bytes in a message body.
+    ///
+    /// It bounds the Content-Length, the total size of all chunks of a chunked body and the
+    /// number of bytes of a body read until EOF. Exceeding it fails with
+    /// `HttpErrno::BodyTooLarge` before the offending bytes are passed to `on_body`.
+    pub fn max_body_size(mut self, size: u64) -> ParserSettings {
+        self.max_body_size = size;
+        self
+    }
+
+    /// Sets the maximum number of bytes in the extensions of a single chunk.
+    ///
+    /// It counts everything between the chunk size and the CRLF that ends the chunk size line.
+    /// Exceeding it fails with `HttpErrno::ChunkExtensionOverflow`.
+    pub fn max_chunk_extension_size(mut self, size: usize) -> ParserSettings {
+        self.max_chunk_extension_size = size;
+        self
+    }
+
+    /// Sets the protocol violations tolerated by the parser.
+    pub fn leniency(mut self, leniency: Leniency) -> ParserSettings {
+        self.leniency = leniency;
+        self
+    }
+
+    /// Sets the handling of obsolete line folding in header values.
+    pub fn obs_fold(mut self, obs_fold: ObsFold) -> ParserSettings {
+        self.obs_fold = obs_fold;
+        self
+    }
+
+    /// Accepts an extension method, such as `LINK` or `QUERY`, in requests.
+    ///
+    /// Methods are case-sensitive. A request with the method has its `method` set to
+    /// `HttpMethod::Extension`, any other method that isn't a variant of `HttpMethod` fails with
+    /// `HttpErrno::InvalidMethod`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use hap_http_parser::*;
+    /// # struct Callback;
+    /// # impl HttpParserCallback for Callback {}
+    /// let settings = ParserSettings::new().method("LINK").method("QUERY");
+    /// let mut parser = HttpParser::with_settings(HttpParserType::Request, settings);
+    ///
+    /// parser.execute(&mut Callback, b"LINK /doc HTTP/1.1\r\n\r\n");
+    /// assert!(parser.method == Some(HttpMethod::Extension("LINK")));
+    /// ```
+    pub fn method(mut self, name: &'static str) -> ParserSettings {
+        self.methods.push(name);
+        self
+    }
+}
+
+impl Default for ParserSettings {
+    fn default() -> ParserSettings {
+        ParserSettings::new()
+    }
+}
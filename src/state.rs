@@ -56,6 +56,7 @@ pub enum State {
     HeaderValueDiscardWsAlmostDone,
     HeaderValueDiscardLws,
     HeaderValueStart,
+    HeaderValueUnfold,
     HeaderValue,
     HeaderValueLws,
 
@@ -64,10 +65,19 @@ pub enum State {
     ChunkSizeStart,
     ChunkSize,
     ChunkParameters,
+    ChunkExtensionStart,
+    ChunkExtensionName,
+    ChunkExtensionNameDone,
+    ChunkExtensionValueStart,
+    ChunkExtensionValue,
+    ChunkExtensionQuotedValue,
+    ChunkExtensionQuotedValueEscape,
     ChunkSizeAlmostDone,
 
     HeadersAlmostDone,
+    ExpectContinue,
     HeadersDone,
+    TrailersDone,
 
     ChunkData,
     ChunkDataAlmostDone,
@@ -80,33 +90,161 @@ pub enum State {
 }
 
 impl State {
+    /// Returns a description of the part of the message the state parses.
+    pub fn name(self) -> &'static str {
+        match self {
+            State::Dead => "after a message with connection close",
+            State::StartReqOrRes => "before a request or response",
+            State::ResOrRespH => "in request method or response protocol",
+            State::StartRes => "before a response",
+            State::ResH => "in response protocol",
+            State::ResHT => "in response protocol",
+            State::ResHTT => "in response protocol",
+            State::ResHTTP => "in response protocol",
+            State::ResE => "in response protocol",
+            State::ResEV => "in response protocol",
+            State::ResEVE => "in response protocol",
+            State::ResEVEN => "in response protocol",
+            State::ResEVENT => "in response protocol",
+            State::ResFirstHttpMajor => "in response major version",
+            State::ResHttpMajor => "in response major version",
+            State::ResFirstHttpMinor => "in response minor version",
+            State::ResHttpMinor => "in response minor version",
+            State::ResFirstStatusCode => "in status code",
+            State::ResStatusCode => "in status code",
+            State::ResStatusStart => "before status reason",
+            State::ResStatus => "in status reason",
+            State::ResLineAlmostDone => "at end of status line",
+            State::StartReq => "before a request",
+            State::ReqMethod => "in request method",
+            State::ReqSpacesBeforeUrl => "before request URL",
+            State::ReqSchema => "in URL schema",
+            State::ReqSchemaSlash => "in URL schema",
+            State::ReqSchemaSlashSlash => "in URL schema",
+            State::ReqServerStart => "in URL host",
+            State::ReqServer => "in URL host",
+            State::ReqServerWithAt => "in URL host",
+            State::ReqPath => "in URL path",
+            State::ReqQueryStringStart => "in URL query string",
+            State::ReqQueryString => "in URL query string",
+            State::ReqFragmentStart => "in URL fragment",
+            State::ReqFragment => "in URL fragment",
+            State::ReqHttpStart => "in request protocol",
+            State::ReqHttpH => "in request protocol",
+            State::ReqHttpHT => "in request protocol",
+            State::ReqHttpHTT => "in request protocol",
+            State::ReqHttpHTTP => "in request protocol",
+            State::ReqFirstHttpMajor => "in request major version",
+            State::ReqHttpMajor => "in request major version",
+            State::ReqFirstHttpMinor => "in request minor version",
+            State::ReqHttpMinor => "in request minor version",
+            State::ReqLineAlmostDone => "at end of request line",
+            State::HeaderFieldStart => "before header field",
+            State::HeaderField => "in header field",
+            State::HeaderValueDiscardWs => "before header value",
+            State::HeaderValueDiscardWsAlmostDone => "before header value",
+            State::HeaderValueDiscardLws => "before header value",
+            State::HeaderValueStart => "before header value",
+            State::HeaderValueUnfold => "in header value",
+            State::HeaderValue => "in header value",
+            State::HeaderValueLws => "at end of header value",
+            State::HeaderAlmostDone => "at end of header",
+            State::ChunkSizeStart => "in chunk size",
+            State::ChunkSize => "in chunk size",
+            State::ChunkParameters => "in chunk extensions",
+            State::ChunkExtensionStart => "in chunk extensions",
+            State::ChunkExtensionName => "in chunk extension name",
+            State::ChunkExtensionNameDone => "in chunk extensions",
+            State::ChunkExtensionValueStart => "in chunk extension value",
+            State::ChunkExtensionValue => "in chunk extension value",
+            State::ChunkExtensionQuotedValue => "in chunk extension value",
+            State::ChunkExtensionQuotedValueEscape => "in chunk extension value",
+            State::ChunkSizeAlmostDone => "at end of chunk size line",
+            State::HeadersAlmostDone => "at end of headers",
+            State::ExpectContinue => "at end of headers",
+            State::HeadersDone => "at end of headers",
+            State::TrailersDone => "at end of trailers",
+            State::ChunkData => "in chunk data",
+            State::ChunkDataAlmostDone => "at end of chunk data",
+            State::ChunkDataDone => "at end of chunk data",
+            State::BodyIdentity => "in body",
+            State::BodyIdentityEof => "in body",
+            State::MessageDone => "at end of message",
+        }
+    }
+
     pub fn is_header_state(self) -> bool {
         self <= State::HeadersDone
     }
+
+    pub fn is_chunk_extension_state(self) -> bool {
+        self >= State::ChunkParameters && self < State::ChunkSizeAlmostDone
+    }
 }
 
 pub enum HeaderState {
     General,
     C,
     CO,
-    CON,
+    Con,
 
     MatchingConnection,
     MatchingProxyConnection,
     MatchingContentLength,
     MatchingTransferEncoding,
     MatchingUpgrade,
+    MatchingExpect,
 
     Connection,
     ContentLength,
     TransferEncoding,
     Upgrade,
+    Expect,
+
+    ContentLengthWs,
 
-    MatchingTransferEncodingChunked,
+    MatchingConnectionTokenStart,
+    MatchingConnectionToken,
     MatchingConnectionKeepAlive,
     MatchingConnectionClose,
+    MatchingConnectionUpgrade,
+    MatchingExpectContinue,
 
-    TransferEncodingChunked,
     ConnectionKeepAlive,
     ConnectionClose,
+    ConnectionUpgrade,
+    ExpectContinue,
+}
+
+impl HeaderState {
+    pub fn is_connection_state(&self) -> bool {
+        matches!(*self,
+                 HeaderState::Connection |
+                 HeaderState::MatchingConnectionTokenStart |
+                 HeaderState::MatchingConnectionToken |
+                 HeaderState::MatchingConnectionKeepAlive |
+                 HeaderState::MatchingConnectionClose |
+                 HeaderState::MatchingConnectionUpgrade |
+                 HeaderState::ConnectionKeepAlive |
+                 HeaderState::ConnectionClose |
+                 HeaderState::ConnectionUpgrade)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum HostState {
+    Dead,
+    UserinfoStart,
+    Userinfo,
+    HostStart,
+    HostV6Start,
+    Host,
+    HostV6,
+    HostV6End,
+    HostV6ZoneStart,
+    HostV6Zone,
+    HostV6ZoneEscape,
+    HostV6ZoneEscapeHex,
+    HostPortStart,
+    HostPort,
 }
@@ -0,0 +1,538 @@
+//! A parser that splits URLs into their components.
+
+use std::net::Ipv6Addr;
+use std::str;
+
+use error::HttpErrno;
+use parser::{is_alpha, is_num, is_alphanum};
+use state::{State, HostState};
+
+/// The position of a component in the URL passed to `parse_url`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct UrlField {
+    /// Offset of the first byte of the component
+    pub offset: usize,
+    /// Length of the component
+    pub len: usize,
+}
+
+impl UrlField {
+    /// Returns the bytes of the component in `url`.
+    pub fn slice<'a>(&self, url: &'a [u8]) -> &'a [u8] {
+        &url[self.offset .. self.offset + self.len]
+    }
+}
+
+/// The components of a URL found by `parse_url`.
+///
+/// Delimiters aren't part of the components: the schema of `http://h/p?q#f` is `http`, the
+/// query `q` and the fragment `f`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct UrlFields {
+    pub schema: Option<UrlField>,
+    pub host: Option<UrlField>,
+    pub port: Option<UrlField>,
+    pub path: Option<UrlField>,
+    pub query: Option<UrlField>,
+    pub fragment: Option<UrlField>,
+    pub userinfo: Option<UrlField>,
+    /// The value of the port component
+    pub port_number: Option<u16>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Component {
+    Schema,
+    Host,
+    Path,
+    Query,
+    Fragment,
+}
+
+impl UrlFields {
+    fn component_mut(&mut self, component: Component) -> &mut Option<UrlField> {
+        match component {
+            Component::Schema => &mut self.schema,
+            Component::Host => &mut self.host,
+            Component::Path => &mut self.path,
+            Component::Query => &mut self.query,
+            Component::Fragment => &mut self.fragment,
+        }
+    }
+}
+
+// Characters of a URL. '#' and '?' are handled by the URL states, see is_url_char for
+// the characters accepted by the url_chars leniency
+static NORMAL_URL_CHARS: [bool; 256] = [
+/*   0 nul    1 soh    2 stx    3 etx    4 eot    5 enq    6 ack    7 bel */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*   8 bs     9 ht    10 nl    11 vt    12 np    13 cr    14 so    15 si */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  16 dle   17 dc1   18 dc2   19 dc3   20 dc4   21 nak   22 syn   23 etb */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  24 can   25 em    26 sub   27 esc   28 fs    29 gs    30 rs    31 us */
+   false,   false,   false,   false,   false,   false,   false,   false,
+/*  32 sp    33 !     34 "     35 #     36 $     37 %     38 &     39 ' */
+   false,   true,    true,    false,   true,    true,    true,    true,
+/*  40 (     41 )     42 *     43 +     44 ,     45 -     46 .     47 / */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  48 0     49 1     50 2     51 3     52 4     53 5     54 6     55 7 */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  56 8     57 9     58 :     59 ;     60 <     61 =     62 >     63 ? */
+   true,    true,    true,    true,    true,    true,    true,    false,
+/*  64 @     65 A     66 B     67 C     68 D     69 E     70 F     71 G */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  72 H     73 I     74 J     75 K     76 L     77 M     78 N     79 O */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  80 P     81 Q     82 R     83 S     84 T     85 U     86 V     87 W */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  88 X     89 Y     90 Z     91 [     92 \     93 ]     94 ^     95 _ */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/*  96 `     97 a     98 b     99 c    100 d    101 e    102 f    103 g */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 104 h    105 i    106 j    107 k    108 l    109 m    110 n    111 o */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 112 p    113 q    114 r    115 s    116 t    117 u    118 v    119 w */
+   true,    true,    true,    true,    true,    true,    true,    true,
+/* 120 x    121 y    122 z    123 {    124 |    125 }    126 ~    127 del */
+   true,    true,    true,    true,    true,    true,    true,    false,
+/* 128-255 */
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+   false,   false,   false,   false,   false,   false,   false,   false,
+];
+
+fn is_normal_url_char(ch: u8) -> bool {
+    NORMAL_URL_CHARS[ch as usize]
+}
+
+fn is_url_char(lenient: bool, ch: u8) -> bool {
+    is_normal_url_char(ch) || (lenient && ((ch & 0x80) > 0 || ch == 9 || ch == 12))
+}
+
+fn is_mark(ch: u8) -> bool {
+    ch == b'-' || ch == b'_' || ch == b'.' || ch == b'!' || ch == b'~' ||
+        ch == b'*' || ch == b'\'' || ch == b'(' || ch == b')'
+}
+
+fn is_userinfo_char(ch: u8) -> bool {
+    is_alphanum(ch) || is_mark(ch) || ch == b'%' ||
+        ch == b';' || ch == b':' || ch == b'&' || ch == b'=' ||
+        ch == b'+' || ch == b'$' || ch == b','
+}
+
+fn is_host_char(ch: u8) -> bool {
+    is_alphanum(ch) || ch == b'.' || ch == b'-' || ch == b'_'
+}
+
+// Our URL parser
+pub fn parse_url_char(lenient: bool, s: State, ch: u8) -> State {
+    if ch == b' ' || ch == b'\r' || ch == b'\n' || (!lenient && (ch == b'\t' || ch == b'\x0C')) { // '\x0C' = '\f'
+        return State::Dead;
+    }
+
+    match s {
+        State::ReqSpacesBeforeUrl => {
+            // Proxied requests are followed by scheme of an absolute URI (alpha).
+            // All methods except CONNECT are followed by '/' or '*'.
+
+            if ch == b'/' || ch == b'*' {
+                return State::ReqPath;
+            }
+
+            if is_alpha(ch) {
+                return State::ReqSchema;
+            }
+        },
+        State::ReqSchema => {
+            if is_alpha(ch) {
+                return s;
+            }
+
+            if ch == b':' {
+                return State::ReqSchemaSlash;
+            }
+        },
+        State::ReqSchemaSlash if ch == b'/' => return State::ReqSchemaSlashSlash,
+        State::ReqSchemaSlashSlash if ch == b'/' => return State::ReqServerStart,
+        State::ReqServerWithAt if ch == b'@' => return State::Dead,
+        State::ReqServerWithAt | State::ReqServerStart | State::ReqServer => {
+            if ch == b'/' {
+                return State::ReqPath;
+            }
+
+            if ch == b'?' {
+                return State::ReqQueryStringStart;
+            }
+
+            if ch == b'@' {
+                return State::ReqServerWithAt;
+            }
+
+            if is_userinfo_char(ch) || ch == b'[' || ch == b']' {
+                return State::ReqServer;
+            }
+        },
+        State::ReqPath => {
+            if is_url_char(lenient, ch) {
+                return s;
+            }
+
+            match ch {
+                b'?' => return State::ReqQueryStringStart,
+                b'#' => return State::ReqFragmentStart,
+                _    => (),
+            }
+        },
+        State::ReqQueryStringStart | State::ReqQueryString => {
+            if is_url_char(lenient, ch) {
+                return State::ReqQueryString;
+            }
+
+            match ch {
+                b'?' => return State::ReqQueryString, // allow extra '?' in query string
+                b'#' => return State::ReqFragmentStart,
+                _    => (),
+            }
+        },
+        State::ReqFragmentStart => {
+            if is_url_char(lenient, ch) {
+                return State::ReqFragment;
+            }
+
+            match ch {
+                b'?' => return State::ReqFragment,
+                b'#' => return s,
+                _    => (),
+            }
+        },
+        State::ReqFragment => {
+            if is_url_char(lenient, ch) {
+                return s;
+            }
+
+            if ch == b'?' || ch == b'#' {
+                return s;
+            }
+        },
+        _ => (),
+    }
+
+    // We should never fall out of the switch above unless there's an error
+    State::Dead
+}
+
+// The error of a character that is invalid in URL state `s`
+pub fn url_errno(s: State) -> HttpErrno {
+    match s {
+        State::ReqServerStart |
+        State::ReqServer |
+        State::ReqServerWithAt => HttpErrno::InvalidHost,
+        State::ReqPath => HttpErrno::InvalidPath,
+        State::ReqQueryStringStart |
+        State::ReqQueryString => HttpErrno::InvalidQueryString,
+        State::ReqFragmentStart |
+        State::ReqFragment => HttpErrno::InvalidFragment,
+        _ => HttpErrno::InvalidUrl,
+    }
+}
+
+pub fn is_server_state(s: State) -> bool {
+    s == State::ReqServerStart || s == State::ReqServer || s == State::ReqServerWithAt
+}
+
+fn is_ipv6_address(address: &[u8]) -> bool {
+    match str::from_utf8(address) {
+        Ok(address) => address.parse::<Ipv6Addr>().is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn parse_host_char(s: HostState, ch: u8) -> HostState {
+    match s {
+        HostState::Userinfo | HostState::UserinfoStart => {
+            if ch == b'@' {
+                return HostState::HostStart;
+            }
+
+            if is_userinfo_char(ch) {
+                return HostState::Userinfo;
+            }
+        },
+        HostState::HostStart => {
+            if ch == b'[' {
+                return HostState::HostV6Start;
+            }
+
+            if is_host_char(ch) {
+                return HostState::Host;
+            }
+        },
+        HostState::Host | HostState::HostV6End => {
+            if s == HostState::Host && is_host_char(ch) {
+                return HostState::Host;
+            }
+
+            if ch == b':' {
+                return HostState::HostPortStart;
+            }
+        },
+        HostState::HostV6 | HostState::HostV6Start => {
+            if s == HostState::HostV6 && ch == b']' {
+                return HostState::HostV6End;
+            }
+
+            if ch.is_ascii_hexdigit() || ch == b':' || ch == b'.' {
+                return HostState::HostV6;
+            }
+
+            if s == HostState::HostV6 && ch == b'%' {
+                return HostState::HostV6ZoneStart;
+            }
+        },
+        HostState::HostV6Zone | HostState::HostV6ZoneStart => {
+            if s == HostState::HostV6Zone && ch == b']' {
+                return HostState::HostV6End;
+            }
+
+            // RFC 6874 Zone ID consists of 1*( unreserved / pct-encoded)
+            if s == HostState::HostV6Zone && ch == b'%' {
+                return HostState::HostV6ZoneEscape;
+            }
+
+            if is_alphanum(ch) || ch == b'.' || ch == b'-' || ch == b'_' || ch == b'~' {
+                return HostState::HostV6Zone;
+            }
+        },
+        HostState::HostV6ZoneEscape if ch.is_ascii_hexdigit() => {
+            return HostState::HostV6ZoneEscapeHex;
+        },
+        HostState::HostV6ZoneEscapeHex if ch.is_ascii_hexdigit() => return HostState::HostV6Zone,
+        HostState::HostPort | HostState::HostPortStart if is_num(ch) => return HostState::HostPort,
+        _ => (),
+    }
+
+    HostState::Dead
+}
+
+// "ffff:ffff:ffff:ffff:ffff:ffff:255.255.255.255"
+const MAX_IPV6_ADDRESS_LEN: usize = 45;
+
+// Validates the server part of a URL one character at a time, so that it may be split
+// between calls of execute. The first error is kept until `finish`, since the characters
+// before '@' turn out to be userinfo instead of a host.
+#[derive(Clone, Copy)]
+pub struct HostParser {
+    state: HostState,
+    errno: Option<HttpErrno>,
+    port: u32,
+    address: [u8; MAX_IPV6_ADDRESS_LEN], // the IPv6 address between '[' and ']' or '%'
+    address_len: usize,
+}
+
+impl HostParser {
+    pub fn new(found_at: bool) -> HostParser {
+        HostParser {
+            state: if found_at { HostState::UserinfoStart } else { HostState::HostStart },
+            errno: Option::None,
+            port: 0,
+            address: [0; MAX_IPV6_ADDRESS_LEN],
+            address_len: 0,
+        }
+    }
+
+    pub fn state(&self) -> HostState {
+        self.state
+    }
+
+    pub fn parse_char(&mut self, ch: u8) {
+        let s = self.state;
+        self.state = parse_host_char(s, ch);
+        match self.state {
+            HostState::Dead if s != HostState::Dead => {
+                self.fail(match s {
+                    HostState::HostPortStart | HostState::HostPort => HttpErrno::InvalidPort,
+                    _ => HttpErrno::InvalidHost,
+                })
+            },
+            HostState::HostV6 => {
+                if self.address_len == MAX_IPV6_ADDRESS_LEN {
+                    return self.fail(HttpErrno::InvalidHost);
+                }
+                self.address[self.address_len] = ch;
+                self.address_len += 1;
+            },
+            HostState::HostV6End | HostState::HostV6ZoneStart if s == HostState::HostV6 &&
+                !is_ipv6_address(&self.address[.. self.address_len]) => {
+                self.fail(HttpErrno::InvalidHost)
+            },
+            HostState::HostPort => {
+                self.port = self.port * 10 + (ch - b'0') as u32;
+                // Ports have a max value of 2^16
+                if self.port > 0xffff {
+                    self.fail(HttpErrno::InvalidPort);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn fail(&mut self, errno: HttpErrno) {
+        self.state = HostState::Dead;
+        if self.errno.is_none() {
+            self.errno = Option::Some(errno);
+        }
+    }
+
+    // Checks that the server part ends with a host or a port, and returns the port
+    pub fn finish(&self) -> Result<Option<u16>, HttpErrno> {
+        if let Some(errno) = self.errno {
+            return Err(errno);
+        }
+
+        match self.state {
+            HostState::Host | HostState::HostV6End => Ok(Option::None),
+            HostState::HostPort => Ok(Option::Some(self.port as u16)),
+            HostState::HostPortStart => Err(HttpErrno::InvalidPort),
+            _ => Err(HttpErrno::InvalidHost),
+        }
+    }
+}
+
+// Splits the server part of the URL into userinfo, host and port
+fn parse_host(url: &[u8], fields: &mut UrlFields, server: UrlField, found_at: bool)
+              -> Result<(), HttpErrno> {
+    let mut parser = HostParser::new(found_at);
+    let mut host = UrlField { offset: server.offset, len: 0 };
+
+    for (index, &ch) in url.iter().enumerate().skip(server.offset).take(server.len) {
+        let s = parser.state();
+        parser.parse_char(ch);
+        let new_s = parser.state();
+        match new_s {
+            HostState::Dead => break,
+            HostState::Host | HostState::HostV6 => {
+                if s != new_s {
+                    host.offset = index;
+                }
+                host.len += 1;
+            },
+            HostState::HostV6ZoneStart |
+            HostState::HostV6Zone |
+            HostState::HostV6ZoneEscape |
+            HostState::HostV6ZoneEscapeHex => host.len += 1,
+            HostState::HostPort => extend(&mut fields.port, s != new_s, index),
+            HostState::Userinfo => extend(&mut fields.userinfo, s != new_s, index),
+            _ => (),
+        }
+    }
+
+    fields.port_number = parser.finish()?;
+    fields.host = Option::Some(host);
+    Ok(())
+}
+
+// Starts a component at `index` or adds the character at `index` to it
+fn extend(field: &mut Option<UrlField>, start: bool, index: usize) {
+    match *field {
+        Some(ref mut field) if !start => field.len += 1,
+        _ => *field = Option::Some(UrlField { offset: index, len: 1 }),
+    }
+}
+
+/// Splits a URL into its components, like `http_parser_parse_url` of joyent/http-parser.
+///
+/// The URL of a CONNECT request must be `host:port`, see `is_connect`. Characters are accepted
+/// as with `Leniency::url_chars`, so any URL passed to `on_url` can be split. Fails with the
+/// error of the invalid component, like `HttpErrno::InvalidHost` or `HttpErrno::InvalidPort`.
+///
+/// # Example
+///
+/// ```
+/// # use hap_http_parser::*;
+/// let url = b"http://user@example.com:8080/accessories?id=1#top";
+/// let fields = parse_url(url, false).unwrap();
+///
+/// assert_eq!(fields.host.unwrap().slice(url), b"example.com");
+/// assert_eq!(fields.userinfo.unwrap().slice(url), b"user");
+/// assert_eq!(fields.path.unwrap().slice(url), b"/accessories");
+/// assert_eq!(fields.query.unwrap().slice(url), b"id=1");
+/// assert_eq!(fields.port_number, Some(8080));
+/// ```
+pub fn parse_url(url: &[u8], is_connect: bool) -> Result<UrlFields, HttpErrno> {
+    if url.is_empty() {
+        return Err(HttpErrno::InvalidUrl);
+    }
+
+    let mut fields = UrlFields::default();
+    let mut s = if is_connect { State::ReqServerStart } else { State::ReqSpacesBeforeUrl };
+    let mut old_component = Option::None;
+    let mut found_at = false;
+
+    for (index, &ch) in url.iter().enumerate() {
+        let old_s = s;
+        s = parse_url_char(true, s, ch);
+        let component = match s {
+            State::Dead => return Err(url_errno(old_s)),
+            // Skip delimiters
+            State::ReqSchemaSlash |
+            State::ReqSchemaSlashSlash |
+            State::ReqServerStart |
+            State::ReqQueryStringStart |
+            State::ReqFragmentStart => continue,
+            State::ReqSchema => Component::Schema,
+            State::ReqServerWithAt => {
+                found_at = true;
+                Component::Host
+            },
+            State::ReqServer => Component::Host,
+            State::ReqPath => Component::Path,
+            State::ReqQueryString => Component::Query,
+            State::ReqFragment => Component::Fragment,
+            _ => unreachable!("unexpected URL state"),
+        };
+
+        extend(fields.component_mut(component), old_component != Option::Some(component), index);
+        old_component = Option::Some(component);
+    }
+
+    // A host must follow the schema, http:///toto is rejected
+    if fields.schema.is_some() && fields.host.is_none() {
+        return Err(HttpErrno::InvalidHost);
+    }
+
+    if let Some(server) = fields.host {
+        parse_host(url, &mut fields, server, found_at)?;
+    }
+
+    // CONNECT requests can only contain "hostname:port"
+    if is_connect {
+        if fields.host.is_none() {
+            return Err(HttpErrno::InvalidHost);
+        }
+
+        if fields.port.is_none() {
+            return Err(HttpErrno::InvalidPort);
+        }
+
+        if fields.schema.is_some() || fields.path.is_some() || fields.query.is_some() ||
+            fields.fragment.is_some() || fields.userinfo.is_some() {
+            return Err(HttpErrno::InvalidUrl);
+        }
+    }
+
+    Ok(fields)
+}
@@ -16,7 +16,7 @@ pub struct Message {
     pub name: String,
     pub raw: String,
     pub tp: HttpParserType,
-    pub strict: bool,
+    pub leniency: Leniency,
     pub method: Option<HttpMethod>,
     pub status_code: Option<u16>,
     pub response_status: Vec<u8>,
@@ -50,7 +50,7 @@ impl Default for Message {
             name: String::new() ,
             raw: String::new(),
             tp: HttpParserType::Both,
-            strict: true,
+            leniency: Leniency::none(),
             method: None,
             status_code: None,
             response_status: vec![],
@@ -84,22 +84,13 @@ pub struct CallbackEmpty;
 
 impl HttpParserCallback for CallbackEmpty {}
 
+#[derive(Default)]
 pub struct CallbackRegular {
     pub num_messages: usize, // maybe not necessary
     pub messages: Vec<Message>,
     pub currently_parsing_eof: bool,
 }
 
-impl Default for CallbackRegular {
-    fn default() -> CallbackRegular {
-        CallbackRegular {
-            num_messages: 0,
-            messages: Vec::new(),
-            currently_parsing_eof: false,
-        }
-    }
-}
-
 impl HttpParserCallback for CallbackRegular {
     fn on_message_begin(&mut self, _ : &mut HttpParser) -> CallbackResult {
         self.messages[self.num_messages].message_begin_cb_called = true;
@@ -441,22 +432,13 @@ impl HttpParserCallback for CallbackPause {
     }
 }
 
+#[derive(Default)]
 pub struct CallbackCountBody {
     pub num_messages: usize, // maybe not necessary
     pub messages: Vec<Message>,
     pub currently_parsing_eof: bool,
 }
 
-impl Default for CallbackCountBody {
-    fn default() -> CallbackCountBody {
-        CallbackCountBody {
-            num_messages: 0,
-            messages: Vec::new(),
-            currently_parsing_eof: false,
-        }
-    }
-}
-
 // find a way to reuse the code in CallbackRegular
 impl HttpParserCallback for CallbackCountBody {
     fn on_message_begin(&mut self, _ : &mut HttpParser) -> CallbackResult {
@@ -569,14 +551,13 @@ impl HttpParserCallback for CallbackCountBody {
 pub fn print_error(errno: HttpErrno, raw: &[u8], error_location: usize) {
     println!("\n*** {} ***\n", errno);
 
-    let len = raw.len();
     let mut this_line = false;
     let mut char_len: u64;
     let mut error_location_line = 0;
     let mut eof = true;
-    for i in (0..len ) {
-        if i == (error_location as usize) { this_line = true; }
-        match raw[i] {
+    for (i, &ch) in raw.iter().enumerate() {
+        if i == error_location { this_line = true; }
+        match ch {
             b'\r' => {
                 char_len = 2;
                 print!("\\r");
@@ -594,7 +575,7 @@ pub fn print_error(errno: HttpErrno, raw: &[u8], error_location: usize) {
             },
             _ => {
                 char_len = 1;
-                print!("{}", (raw[i] as char));
+                print!("{}", (ch as char));
             },
         }
         if !this_line { error_location_line += char_len; }
@@ -604,7 +585,7 @@ pub fn print_error(errno: HttpErrno, raw: &[u8], error_location: usize) {
         println!("[eof]");
     }
 
-    for _ in (0..error_location_line) {
+    for _ in 0..error_location_line {
         print!(" ");
     }
     println!("^\n\nerror location: {}", error_location);
@@ -631,7 +612,21 @@ pub fn assert_eq_message(actual: &Message, expected: &Message) {
 
     // Check URL components; we can't do this w/ CONNECT since it doesn't
     // send us a well-formed URL.
-    // TODO add after implementing http_parser_parse_url()
+    if expected.tp == HttpParserType::Request && expected.method != Some(HttpMethod::Connect) {
+        let url = &actual.request_url;
+        let fields = parse_url(url, false).unwrap();
+        let component = |field: Option<UrlField>| match field {
+            Some(field) => String::from_utf8_lossy(field.slice(url)).to_string(),
+            None => String::new(),
+        };
+
+        assert_eq!(component(fields.host), expected.host);
+        assert_eq!(component(fields.userinfo), expected.userinfo);
+        assert_eq!(component(fields.path), expected.request_path);
+        assert_eq!(component(fields.query), expected.query_string);
+        assert_eq!(component(fields.fragment), expected.fragment);
+        assert_eq!(fields.port_number.unwrap_or(0), expected.port);
+    }
 
     if expected.body_size > 0 {
         assert_eq!(actual.body_size, expected.body_size);
@@ -641,9 +636,9 @@ pub fn assert_eq_message(actual: &Message, expected: &Message) {
 
     assert_eq!(actual.headers.len(), expected.headers.len());
 
-    for i in (0..actual.headers.len()) {
-        assert_eq!(actual.headers[i as usize][0], expected.headers[i as usize][0]);
-        assert_eq!(actual.headers[i as usize][1], expected.headers[i as usize][1]);
+    for i in 0..actual.headers.len() {
+        assert_eq!(actual.headers[i][0], expected.headers[i][0]);
+        assert_eq!(actual.headers[i][1], expected.headers[i][1]);
     }
 
     assert_eq!(actual.upgrade, expected.upgrade);
@@ -652,9 +647,9 @@ pub fn assert_eq_message(actual: &Message, expected: &Message) {
 pub fn test_message(message: &Message) {
     let raw = &message.raw;
     let raw_len = raw.len();
-    for i in (0..raw_len) {
-        let mut hp = HttpParser::new(message.tp);
-        hp.strict = message.strict;
+    for i in 0..raw_len {
+        let mut hp = HttpParser::with_settings(message.tp,
+                                               ParserSettings::new().leniency(message.leniency));
 
         let mut cb = CallbackRegular{..Default::default()};
         cb.messages.push(Message{..Default::default()});
@@ -707,13 +702,12 @@ pub fn test_message(message: &Message) {
 pub fn test_message_pause(msg: &Message) {
     let mut raw : &str = &msg.raw;
 
-    let mut hp = HttpParser::new(msg.tp);
-    hp.strict = msg.strict;
+    let mut hp = HttpParser::with_settings(msg.tp, ParserSettings::new().leniency(msg.leniency));
 
     let mut cb = CallbackPause{..Default::default()};
     cb.messages.push(Message{..Default::default()});
 
-    while raw.len() > 0 {
+    while !raw.is_empty() {
         cb.paused = false;
         let read = hp.execute(&mut cb, raw.as_bytes());
 
@@ -746,6 +740,24 @@ pub fn test_message_pause(msg: &Message) {
     assert_eq_message(&cb.messages[0], msg);
 }
 
+// Tolerates every violation tolerated by any of the messages
+fn combined_leniency(messages: &[&Message]) -> Leniency {
+    let mut leniency = Leniency::none();
+
+    for m in messages.iter() {
+        leniency.bare_lf |= m.leniency.bare_lf;
+        leniency.header_name_spaces |= m.leniency.header_name_spaces;
+        leniency.chunk_crlf |= m.leniency.chunk_crlf;
+        leniency.keep_alive |= m.leniency.keep_alive;
+        leniency.protocol |= m.leniency.protocol;
+        leniency.url_chars |= m.leniency.url_chars;
+        leniency.ambiguous_framing |= m.leniency.ambiguous_framing;
+        leniency.request_target |= m.leniency.request_target;
+    }
+
+    leniency
+}
+
 fn count_parsed_messages(messages: &[&Message]) -> usize {
     let mut i: usize = 0;
     let len = messages.len();
@@ -771,8 +783,8 @@ pub fn test_multiple3(r1: &Message, r2: &Message, r3: &Message) {
     total.push_str(&r2.raw);
     total.push_str(&r3.raw);
 
-    let mut hp = HttpParser::new(r1.tp);
-    hp.strict = r1.strict && r2.strict && r3.strict;
+    let settings = ParserSettings::new().leniency(combined_leniency(&messages));
+    let mut hp = HttpParser::with_settings(r1.tp, settings);
 
     let mut cb = CallbackRegular{..Default::default()};
     cb.messages.push(Message{..Default::default()});
@@ -824,8 +836,8 @@ fn upgrade_message_fix(cb: &mut CallbackRegular, body: &str, read: usize, msgs:
     for m in msgs.iter() {
         off += m.raw.len();
 
-        if m.upgrade.is_some() {
-            let upgrade_len = m.upgrade.as_ref().unwrap().len();
+        if let Some(ref upgrade) = m.upgrade {
+            let upgrade_len = upgrade.len();
 
             off -= upgrade_len;
 
@@ -841,7 +853,7 @@ fn upgrade_message_fix(cb: &mut CallbackRegular, body: &str, read: usize, msgs:
 }
 
 fn print_test_scan_error(i: usize, j: usize, buf1: &[u8], buf2: &[u8], buf3: &[u8]) {
-    print!("i={}  j={}\n", i, j);
+    println!("i={}  j={}", i, j);
     unsafe {
         print!("buf1 ({}) {}\n\n", buf1.len(), str::from_utf8_unchecked(buf1));
         print!("buf2 ({}) {}\n\n", buf2.len(), str::from_utf8_unchecked(buf2));
@@ -861,10 +873,11 @@ pub fn test_scan(r1: &Message, r2: &Message, r3: &Message) {
     let message_count = count_parsed_messages(&[r1, r2, r3]);
 
     for &is_type_both in [false, true].iter() {
-        for j in (2..total_len) {
-            for i in (1..j) {
-                let mut hp = HttpParser::new(if is_type_both { HttpParserType::Both } else { r1.tp });
-                hp.strict = r1.strict && r2.strict && r3.strict;
+        for j in 2..total_len {
+            for i in 1..j {
+                let settings = ParserSettings::new().leniency(combined_leniency(&[r1, r2, r3]));
+                let mut hp = HttpParser::with_settings(
+                    if is_type_both { HttpParserType::Both } else { r1.tp }, settings);
 
                 let mut cb = CallbackRegular{..Default::default()};
                 cb.messages.push(Message{..Default::default()});
@@ -0,0 +1,109 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+#[derive(Default, PartialEq, Debug)]
+struct Framing {
+    chunked: bool,
+    content_length: Option<u64>,
+    remaining_body: Option<u64>,
+    connection_close: bool,
+    connection_keep_alive: bool,
+    upgrade_header: bool,
+}
+
+#[derive(Default)]
+struct CallbackFraming {
+    framing: Framing,
+    remaining_on_body: Vec<Option<u64>>,
+}
+
+impl HttpParserCallback for CallbackFraming {
+    fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        self.framing = Framing {
+            chunked: parser.is_chunked(),
+            content_length: parser.content_length(),
+            remaining_body: parser.remaining_body(),
+            connection_close: parser.has_connection_close(),
+            connection_keep_alive: parser.has_connection_keep_alive(),
+            upgrade_header: parser.has_upgrade_header(),
+        };
+        Ok(ParseAction::None)
+    }
+
+    fn on_body(&mut self, parser : &mut HttpParser, _ : &[u8]) -> CallbackResult {
+        self.remaining_on_body.push(parser.remaining_body());
+        Ok(ParseAction::None)
+    }
+}
+
+fn parse(data: &[&str]) -> CallbackFraming {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackFraming{..Default::default()};
+
+    for d in data {
+        assert_eq!(hp.execute(&mut cb, d.as_bytes()), d.len());
+    }
+    cb
+}
+
+#[test]
+fn test_content_length() {
+    let cb = parse(&["POST / HTTP/1.1\r\n\
+                      Content-Length: 10\r\n\
+                      Connection: keep-alive\r\n\
+                      \r\n\
+                      0123", "456789"]);
+
+    assert_eq!(cb.framing, Framing {
+        content_length: Option::Some(10),
+        remaining_body: Option::Some(10),
+        connection_keep_alive: true,
+        ..Default::default()
+    });
+    assert_eq!(cb.remaining_on_body, vec![Option::Some(6), Option::Some(0)]);
+}
+
+#[test]
+fn test_chunked() {
+    let cb = parse(&["POST / HTTP/1.1\r\n\
+                      Transfer-Encoding: chunked\r\n\
+                      Connection: close\r\n\
+                      \r\n\
+                      5\r\nhello\r\n\
+                      0\r\n\r\n"]);
+
+    assert_eq!(cb.framing, Framing {
+        chunked: true,
+        connection_close: true,
+        ..Default::default()
+    });
+    assert_eq!(cb.remaining_on_body, vec![Option::None]);
+}
+
+#[test]
+fn test_upgrade_header() {
+    let cb = parse(&["GET /stream HTTP/1.1\r\n\
+                      Connection: Upgrade\r\n\
+                      Upgrade: websocket\r\n\
+                      \r\n"]);
+
+    assert!(cb.framing.upgrade_header);
+    assert!(!cb.framing.chunked);
+    assert_eq!(cb.framing.content_length, Option::None);
+}
+
+#[test]
+fn test_accessors_are_reset_between_messages() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackFraming{..Default::default()};
+
+    hp.execute(&mut cb, b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nok");
+    assert_eq!(cb.framing.content_length, Option::Some(2));
+
+    hp.execute(&mut cb, b"GET / HTTP/1.1\r\n\r\n");
+    assert_eq!(cb.framing, Framing{..Default::default()});
+}
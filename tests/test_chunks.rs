@@ -0,0 +1,180 @@
+extern crate hap_http_parser;
+
+use std::str;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ParserSettings};
+
+pub mod helper;
+
+const CHUNKED_WITH_EXTENSIONS: &str = "POST /chunked HTTP/1.1\r\n\
+                                       Transfer-Encoding: chunked\r\n\
+                                       \r\n\
+                                       5;name=value\r\nhello\r\n\
+                                       6 ;b = \"q\\\"s\" ; a\r\n world\r\n\
+                                       0\r\n\
+                                       Vary: *\r\n\
+                                       \r\n";
+
+#[derive(Default)]
+struct CallbackChunks {
+    events: Vec<String>,
+}
+
+impl HttpParserCallback for CallbackChunks {
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.push("body", data);
+        Ok(ParseAction::None)
+    }
+
+    fn on_chunk_header(&mut self, _ : &mut HttpParser, size : u64) -> CallbackResult {
+        self.events.push(format!("header {}", size));
+        Ok(ParseAction::None)
+    }
+
+    fn on_chunk_extension_name(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.push("name", data);
+        Ok(ParseAction::None)
+    }
+
+    fn on_chunk_extension_value(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.push("value", data);
+        Ok(ParseAction::None)
+    }
+
+    fn on_chunk_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.events.push("complete".to_string());
+        Ok(ParseAction::None)
+    }
+
+    fn on_trailers_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.events.push("trailers".to_string());
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.events.push("message".to_string());
+        Ok(ParseAction::None)
+    }
+}
+
+impl CallbackChunks {
+    // Joins the data of consecutive calls of the same callback
+    fn push(&mut self, event: &str, data: &[u8]) {
+        let prefix = format!("{} ", event);
+        let data = str::from_utf8(data).unwrap();
+        if let Some(last) = self.events.last_mut() {
+            if last.starts_with(&prefix) {
+                last.push_str(data);
+                return;
+            }
+        }
+        self.events.push(prefix + data);
+    }
+}
+
+fn expected_events() -> Vec<String> {
+    ["name name", "value value", "header 5", "body hello", "complete",
+     "name b", "value \"q\\\"s\"", "name a", "header 6", "body  world", "complete",
+     "header 0", "trailers", "complete", "message"].iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_chunk_callbacks() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackChunks{..Default::default()};
+
+    let parsed = hp.execute(&mut cb, CHUNKED_WITH_EXTENSIONS.as_bytes());
+
+    assert!(hp.errno.is_none());
+    assert_eq!(parsed, CHUNKED_WITH_EXTENSIONS.len());
+    assert_eq!(cb.events, expected_events());
+}
+
+#[test]
+fn test_chunk_callbacks_byte_by_byte() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackChunks{..Default::default()};
+
+    for b in CHUNKED_WITH_EXTENSIONS.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert_eq!(cb.events, expected_events());
+}
+
+#[test]
+fn test_invalid_chunk_extension() {
+    let invalid = ["5;\r\n", "5;=value\r\n", "5;name=\r\n", "5;na(me\r\n",
+                   "5;name=\"value\r\n", "5 x\r\n", "5;name=\"value\"x\r\n"];
+    for ext in invalid.iter() {
+        let data = format!("POST / HTTP/1.1\r\n\
+                            Transfer-Encoding: chunked\r\n\
+                            \r\n\
+                            {}hello\r\n\
+                            0\r\n\r\n", ext);
+        let mut hp = HttpParser::new(HttpParserType::Request);
+        let mut cb = helper::CallbackEmpty;
+
+        hp.execute(&mut cb, data.as_bytes());
+
+        assert!(hp.errno == Option::Some(HttpErrno::InvalidChunkExtension), "{:?}", ext);
+    }
+}
+
+#[test]
+fn test_max_chunk_extension_size() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5;a=b\r\nhello\r\n\
+                5 ;c=d\r\nworld\r\n\
+                0\r\n\r\n";
+    assert!(parse(ParserSettings::new().max_chunk_extension_size(5), data).is_none());
+    assert!(parse(ParserSettings::new().max_chunk_extension_size(4), data) ==
+            Option::Some(HttpErrno::ChunkExtensionOverflow));
+}
+
+#[test]
+fn test_unbounded_chunk_extension() {
+    let mut data = "POST / HTTP/1.1\r\n\
+                    Transfer-Encoding: chunked\r\n\
+                    \r\n\
+                    5;a=".to_string();
+    data.push_str(&"b".repeat(64 * 1024));
+
+    assert!(parse(ParserSettings::new(), &data) ==
+            Option::Some(HttpErrno::ChunkExtensionOverflow));
+}
+
+#[test]
+fn test_chunk_callback_error() {
+    struct CallbackFailingChunk;
+
+    impl HttpParserCallback for CallbackFailingChunk {
+        fn on_chunk_header(&mut self, _ : &mut HttpParser, size : u64) -> CallbackResult {
+            if size > 4 {
+                return Err("chunk too large".to_string());
+            }
+            Ok(ParseAction::None)
+        }
+    }
+
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackFailingChunk;
+
+    hp.execute(&mut cb, CHUNKED_WITH_EXTENSIONS.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::CBChunkHeader));
+}
+
+fn parse(settings: ParserSettings, data: &str) -> Option<HttpErrno> {
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    if hp.errno.is_none() {
+        assert_eq!(parsed, data.len());
+    }
+    hp.errno
+}
@@ -0,0 +1,104 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, CallbackResult,
+                      ParseAction};
+
+pub mod helper;
+
+#[derive(Default)]
+struct CallbackConnection {
+    tokens: Vec<String>,
+}
+
+impl HttpParserCallback for CallbackConnection {
+    fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        self.tokens = parser.connection_tokens().iter().map(|t| t.to_string()).collect();
+        Ok(ParseAction::None)
+    }
+}
+
+fn parse(tp: HttpParserType, data: &str) -> (HttpParser, Vec<String>) {
+    let mut hp = HttpParser::new(tp);
+    let mut cb = CallbackConnection{..Default::default()};
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.errno.is_none());
+    (hp, cb.tokens)
+}
+
+#[test]
+fn test_keep_alive_in_list() {
+    let data = "GET / HTTP/1.0\r\n\
+                Connection: keep-alive, Upgrade-Insecure-Requests\r\n\
+                \r\n";
+    let (hp, tokens) = parse(HttpParserType::Request, data);
+
+    assert!(hp.has_connection_keep_alive());
+    assert!(hp.http_should_keep_alive());
+    assert_eq!(tokens, vec!["keep-alive", "Upgrade-Insecure-Requests"]);
+}
+
+#[test]
+fn test_close_in_list() {
+    let data = "HTTP/1.1 200 OK\r\n\
+                Connection: TE,CLOSE\r\n\
+                Content-Length: 0\r\n\
+                \r\n";
+    let (hp, tokens) = parse(HttpParserType::Response, data);
+
+    assert!(hp.has_connection_close());
+    assert!(!hp.http_should_keep_alive());
+    assert_eq!(tokens, vec!["TE", "CLOSE"]);
+}
+
+#[test]
+fn test_close_after_tab() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: keep-alive,\tclose\r\n\
+                \r\n";
+    let (hp, tokens) = parse(HttpParserType::Request, data);
+
+    assert!(hp.has_connection_close());
+    assert!(!hp.http_should_keep_alive());
+    assert_eq!(tokens, vec!["keep-alive", "close"]);
+}
+
+#[test]
+fn test_tokens_across_headers() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: X-Trace ,\r\n\
+                Proxy-Connection: keep-alive\r\n\
+                Connection: upgrade,\r\n \
+                X-Forwarded-For\r\n\
+                Upgrade: websocket\r\n\
+                \r\n";
+    let (hp, tokens) = parse(HttpParserType::Request, data);
+
+    assert!(hp.upgrade);
+    assert_eq!(tokens, vec!["X-Trace", "keep-alive", "upgrade", "X-Forwarded-For"]);
+}
+
+#[test]
+fn test_tokens_byte_by_byte() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: close, Keep-Alive\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackConnection{..Default::default()};
+
+    for b in data.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert!(hp.has_connection_close());
+    assert!(hp.has_connection_keep_alive());
+    assert_eq!(cb.tokens, vec!["close", "Keep-Alive"]);
+}
+
+#[test]
+fn test_no_connection_header() {
+    let (hp, tokens) = parse(HttpParserType::Request, "GET / HTTP/1.1\r\n\r\n");
+
+    assert!(tokens.is_empty());
+    assert!(hp.http_should_keep_alive());
+}
@@ -6,13 +6,13 @@ pub mod helper;
 
 macro_rules! content_length(
     ($len:expr) => (
-        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", $len);
+        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", $len)
     );
 );
 
 macro_rules! chunk_content(
     ($len:expr) => (
-        format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{}\r\n...", $len);
+        format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{}\r\n...", $len)
     );
 );
 
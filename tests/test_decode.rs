@@ -0,0 +1,74 @@
+extern crate hap_http_parser;
+
+use std::borrow::Cow;
+
+use hap_http_parser::{percent_decode_path, HttpErrno, Query};
+
+fn pairs(query: &str) -> Vec<(String, String)> {
+    Query::new(query.as_bytes())
+        .map(|(key, value)| (String::from_utf8(key.into_owned()).unwrap(),
+                             String::from_utf8(value.into_owned()).unwrap()))
+        .collect()
+}
+
+fn pair(key: &str, value: &str) -> (String, String) {
+    (key.to_string(), value.to_string())
+}
+
+#[test]
+fn test_query() {
+    assert_eq!(pairs("id=1.4,1.5&meta=1&ev=1"),
+               vec![pair("id", "1.4,1.5"), pair("meta", "1"), pair("ev", "1")]);
+    assert_eq!(pairs(""), vec![]);
+}
+
+#[test]
+fn test_query_decoding() {
+    assert_eq!(pairs("name=Living+Room%20Lamp&a%3Db=%26"),
+               vec![pair("name", "Living Room Lamp"), pair("a=b", "&")]);
+    assert_eq!(pairs("p=100%&q=%4&r=%zz"), vec![pair("p", "100%"), pair("q", "%4"), pair("r", "%zz")]);
+}
+
+#[test]
+fn test_query_pairs() {
+    assert_eq!(pairs("ev&&id=&=1&x=a=b&"),
+               vec![pair("ev", ""), pair("id", ""), pair("", "1"), pair("x", "a=b")]);
+    assert_eq!(pairs("id=1&id=2"), vec![pair("id", "1"), pair("id", "2")]);
+}
+
+#[test]
+fn test_query_borrows() {
+    let mut query = Query::new(b"id=1.4&name=a+b");
+
+    let (key, value) = query.next().unwrap();
+    assert!(matches!(key, Cow::Borrowed(_)) && matches!(value, Cow::Borrowed(_)));
+    let (_, value) = query.next().unwrap();
+    assert!(matches!(value, Cow::Owned(_)));
+    assert!(query.next().is_none());
+}
+
+#[test]
+fn test_decode_path() {
+    assert_eq!(&*percent_decode_path(b"/accessories").unwrap(), b"/accessories");
+    assert_eq!(&*percent_decode_path(b"/a%2Fb/c+d/%C3%A9").unwrap(), "/a/b/c+d/é".as_bytes());
+    assert_eq!(&*percent_decode_path(b"/a/.../b..").unwrap(), b"/a/.../b..");
+}
+
+#[test]
+fn test_decode_path_errors() {
+    let paths: [&[u8]; 8] = [
+        b"/a%00b",
+        b"/a%zzb",
+        b"/a%4",
+        b"/a/../b",
+        b"/..",
+        b"/a/%2e%2E/b",
+        b"/a/.%2e",
+        b"/a%2F..%2Fb",
+    ];
+
+    for path in &paths {
+        assert_eq!(percent_decode_path(path), Err(HttpErrno::InvalidPath),
+                   "{}", String::from_utf8_lossy(path));
+    }
+}
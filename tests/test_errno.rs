@@ -0,0 +1,99 @@
+extern crate hap_http_parser;
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpErrno, ParseError};
+
+pub mod helper;
+
+const ALL_ERRNOS: [HttpErrno; 49] = [
+    HttpErrno::CBMessageBegin, HttpErrno::CBUrl, HttpErrno::CBHeaderField,
+    HttpErrno::CBHeaderValue, HttpErrno::CBHeadersComplete, HttpErrno::CBBody,
+    HttpErrno::CBMessageComplete, HttpErrno::CBStatus, HttpErrno::CBTrailerField,
+    HttpErrno::CBTrailerValue, HttpErrno::CBTrailersComplete, HttpErrno::CBChunkHeader,
+    HttpErrno::CBChunkExtensionName, HttpErrno::CBChunkExtensionValue,
+    HttpErrno::CBChunkComplete, HttpErrno::CBExpectContinue, HttpErrno::InvalidEofState, HttpErrno::HeaderOverflow,
+    HttpErrno::StartLineOverflow, HttpErrno::UrlOverflow, HttpErrno::HeaderCountOverflow,
+    HttpErrno::HeaderValueOverflow, HttpErrno::ClosedConnection, HttpErrno::InvalidVersion,
+    HttpErrno::InvalidStatus, HttpErrno::InvalidMethod, HttpErrno::InvalidUrl,
+    HttpErrno::InvalidHost, HttpErrno::InvalidPort, HttpErrno::InvalidPath,
+    HttpErrno::InvalidQueryString, HttpErrno::InvalidFragment, HttpErrno::LFExpected,
+    HttpErrno::InvalidHeaderToken, HttpErrno::ObsFold, HttpErrno::InvalidContentLength,
+    HttpErrno::UnexpectedContentLength, HttpErrno::DuplicateContentLength,
+    HttpErrno::MissingContentLength, HttpErrno::InvalidTransferEncoding,
+    HttpErrno::InvalidChunkSize, HttpErrno::InvalidChunkExtension,
+    HttpErrno::ChunkExtensionOverflow, HttpErrno::BodyTooLarge, HttpErrno::InvalidConstant,
+    HttpErrno::InvalidInternalState, HttpErrno::Strict, HttpErrno::Paused, HttpErrno::Unknown,
+];
+
+#[test]
+fn test_joyent_names_and_codes() {
+    assert_eq!(HttpErrno::CBMessageBegin.name(), "HPE_CB_message_begin");
+    assert_eq!(HttpErrno::CBMessageBegin.code(), 1);
+    assert_eq!(HttpErrno::CBChunkComplete.name(), "HPE_CB_chunk_complete");
+    assert_eq!(HttpErrno::CBChunkComplete.code(), 10);
+    assert_eq!(HttpErrno::InvalidEofState.name(), "HPE_INVALID_EOF_STATE");
+    assert_eq!(HttpErrno::InvalidEofState.code(), 11);
+    assert_eq!(HttpErrno::Paused.name(), "HPE_PAUSED");
+    assert_eq!(HttpErrno::Paused.code(), 31);
+    assert_eq!(HttpErrno::Unknown.name(), "HPE_UNKNOWN");
+    assert_eq!(HttpErrno::Unknown.code(), 32);
+}
+
+#[test]
+fn test_names_and_codes_are_unique() {
+    let names: HashSet<&str> = ALL_ERRNOS.iter().map(|e| e.name()).collect();
+    let codes: HashSet<u32> = ALL_ERRNOS.iter().map(|e| e.code()).collect();
+
+    assert_eq!(names.len(), ALL_ERRNOS.len());
+    assert_eq!(codes.len(), ALL_ERRNOS.len());
+    assert!(ALL_ERRNOS.iter().all(|e| e.name().starts_with("HPE_")));
+}
+
+#[test]
+fn test_categories() {
+    for errno in ALL_ERRNOS.iter() {
+        let categories = [errno.is_callback_error(), errno.is_limit_error(),
+                          errno.is_protocol_error()];
+        assert!(categories.iter().filter(|c| **c).count() <= 1, "{}", errno.name());
+        assert_eq!(errno.is_callback_error(), errno.name().starts_with("HPE_CB_"));
+    }
+
+    assert!(HttpErrno::InvalidHeaderToken.is_protocol_error());
+    assert!(HttpErrno::Strict.is_protocol_error());
+    assert!(HttpErrno::HeaderOverflow.is_limit_error());
+    assert!(!HttpErrno::Paused.is_protocol_error());
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(HttpErrno::Paused.to_string(), "parser is paused");
+    assert_eq!(format!("{:?}", HttpErrno::InvalidUrl), "InvalidUrl");
+}
+
+#[test]
+fn test_std_error() {
+    fn parse(data: &str) -> Result<(), Box<dyn Error>> {
+        let mut hp = HttpParser::new(HttpParserType::Request);
+        hp.execute(&mut helper::CallbackEmpty, data.as_bytes());
+        match hp.errno {
+            Some(errno) => Err(errno.into()),
+            None => Ok(()),
+        }
+    }
+
+    assert!(parse("GET / HTTP/1.1\r\n\r\n").is_ok());
+    let err = parse("GET / HTTP/1.1\r\nAcc(ept: */*\r\n\r\n").unwrap_err();
+    assert_eq!(err.to_string(), "invalid character in header");
+
+    let err: Box<dyn Error> = Box::new(ParseError {
+        errno: HttpErrno::InvalidUrl,
+        offset: 4,
+        line: 1,
+        column: 5,
+        state: "in URL path",
+        reason: Option::None,
+    });
+    assert_eq!(err.to_string(), "invalid URL at line 1, column 5 (in URL path, offset 4)");
+}
@@ -0,0 +1,101 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ResponseType};
+
+pub mod helper;
+
+const SESSION: &str = "HTTP/1.1 200 OK\r\n\
+                       Content-Type: application/hap+json\r\n\
+                       Content-Length: 2\r\n\
+                       \r\n\
+                       {}\
+                       EVENT/1.0 200 OK\r\n\
+                       Content-Type: application/hap+json\r\n\
+                       Content-Length: 5\r\n\
+                       \r\n\
+                       event\
+                       EVENT/1.0 200 OK\r\n\
+                       Connection: close\r\n\
+                       Content-Length: 0\r\n\
+                       \r\n\
+                       HTTP/1.1 204 No Content\r\n\
+                       \r\n";
+
+#[derive(Default)]
+struct CallbackSession {
+    messages: Vec<(Option<ResponseType>, bool, String)>,
+    body: String,
+}
+
+impl HttpParserCallback for CallbackSession {
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body.push_str(&String::from_utf8_lossy(data));
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        let body = self.body.split_off(0);
+        self.messages.push((parser.response_type, parser.http_should_keep_alive(), body));
+        Ok(ParseAction::None)
+    }
+}
+
+fn expected_messages() -> Vec<(Option<ResponseType>, bool, String)> {
+    vec![(Option::Some(ResponseType::Http), true, "{}".to_string()),
+         (Option::Some(ResponseType::Event), true, "event".to_string()),
+         (Option::Some(ResponseType::Event), true, "".to_string()),
+         (Option::Some(ResponseType::Http), true, "".to_string())]
+}
+
+#[test]
+fn test_session() {
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = CallbackSession{..Default::default()};
+
+    assert_eq!(hp.execute(&mut cb, SESSION.as_bytes()), SESSION.len());
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.messages, expected_messages());
+}
+
+#[test]
+fn test_session_byte_by_byte() {
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = CallbackSession{..Default::default()};
+
+    for b in SESSION.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert_eq!(cb.messages, expected_messages());
+}
+
+#[test]
+fn test_event_without_content_length() {
+    let data = "EVENT/1.0 200 OK\r\n\
+                Content-Type: application/hap+json\r\n\
+                \r\n\
+                {}";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::MissingContentLength));
+}
+
+#[test]
+fn test_chunked_event() {
+    let data = "EVENT/1.0 200 OK\r\n\
+                Transfer-Encoding: chunked\r\n\
+                Content-Length: 2\r\n\
+                \r\n\
+                2\r\n{}\r\n\
+                0\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::InvalidTransferEncoding));
+}
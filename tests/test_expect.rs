@@ -0,0 +1,182 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+const EXPECT_CONTINUE: &str = "POST /resource HTTP/1.1\r\n\
+                               Expect: 100-Continue\r\n\
+                               Content-Length: 5\r\n\
+                               \r\n\
+                               hello";
+
+struct CallbackExpect {
+    action: ParseAction,
+    expect_continue_calls: usize,
+    body: String,
+    messages: usize,
+}
+
+impl CallbackExpect {
+    fn new(action: ParseAction) -> CallbackExpect {
+        CallbackExpect { action, expect_continue_calls: 0, body: String::new(), messages: 0 }
+    }
+}
+
+impl HttpParserCallback for CallbackExpect {
+    fn on_expect_continue(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        assert!(parser.has_expect_continue());
+        assert!(self.body.is_empty());
+        self.expect_continue_calls += 1;
+        Ok(self.action.clone())
+    }
+
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body.push_str(&String::from_utf8_lossy(data));
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.messages += 1;
+        Ok(ParseAction::None)
+    }
+}
+
+#[test]
+fn test_expect_continue_accepted() {
+    let data = EXPECT_CONTINUE;
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackExpect::new(ParseAction::None);
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert_eq!(cb.body, "hello");
+    assert_eq!(cb.messages, 1);
+    assert!(hp.http_should_keep_alive());
+}
+
+#[test]
+fn test_expect_continue_rejected() {
+    let data = EXPECT_CONTINUE;
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackExpect::new(ParseAction::SkipBody);
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert_eq!(cb.messages, 1);
+    assert!(cb.body.is_empty());
+    assert!(!hp.http_should_keep_alive());
+
+    // The body that was sent anyway isn't taken for another request
+    assert_eq!(parsed, data.len() - "hello".len());
+    assert!(hp.errno == Option::Some(HttpErrno::ClosedConnection));
+}
+
+#[test]
+fn test_expect_continue_paused() {
+    let data = EXPECT_CONTINUE;
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackExpect::new(ParseAction::Pause);
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.is_paused());
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert!(cb.body.is_empty());
+
+    // The server decided, the client sends the body
+    hp.resume();
+    cb.action = ParseAction::None;
+    assert_eq!(hp.execute(&mut cb, &data.as_bytes()[parsed..]), data.len() - parsed);
+
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert_eq!(cb.body, "hello");
+    assert_eq!(cb.messages, 1);
+}
+
+#[test]
+fn test_expect_continue_error() {
+    struct CallbackRefuse;
+
+    impl HttpParserCallback for CallbackRefuse {
+        fn on_expect_continue(&mut self, _ : &mut HttpParser) -> CallbackResult {
+            Err("too large".to_string())
+        }
+    }
+
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    hp.execute(&mut CallbackRefuse, EXPECT_CONTINUE.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::CBExpectContinue));
+    assert_eq!(hp.callback_error(), Option::Some("too large"));
+}
+
+#[test]
+fn test_expect_continue_not_called() {
+    let requests = [
+        // No body
+        "POST / HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 0\r\n\r\n",
+        // HTTP/1.0 clients don't wait for 100 Continue
+        "POST / HTTP/1.0\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\nhello",
+        // Other expectations
+        "POST / HTTP/1.1\r\nExpect: 100-continued\r\nContent-Length: 5\r\n\r\nhello",
+        "POST / HTTP/1.1\r\nExpected: 100-continue\r\nContent-Length: 5\r\n\r\nhello",
+    ];
+
+    for data in requests.iter() {
+        let mut hp = HttpParser::new(HttpParserType::Request);
+        let mut cb = CallbackExpect::new(ParseAction::SkipBody);
+
+        assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+        assert_eq!(cb.expect_continue_calls, 0, "{}", data);
+        assert_eq!(cb.messages, 1);
+    }
+}
+
+#[test]
+fn test_expect_continue_chunked() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                Expect: 100-continue\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackExpect::new(ParseAction::None);
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert_eq!(cb.body, "hello");
+}
+
+#[test]
+fn test_expect_continue_rejected_after_pause() {
+    let data = EXPECT_CONTINUE;
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackExpect::new(ParseAction::Pause);
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    assert!(hp.is_paused());
+
+    // The server decided to refuse the body
+    hp.reject_expect();
+    hp.resume();
+    let rest = hp.execute(&mut cb, &data.as_bytes()[parsed..]);
+
+    assert_eq!(cb.expect_continue_calls, 1);
+    assert_eq!(cb.messages, 1);
+    assert!(cb.body.is_empty());
+    assert!(!hp.http_should_keep_alive());
+    assert_eq!(parsed + rest, data.len() - "hello".len());
+    assert!(hp.errno == Option::Some(HttpErrno::ClosedConnection));
+}
+
+#[test]
+#[should_panic]
+fn test_reject_expect_not_paused() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    hp.reject_expect();
+}
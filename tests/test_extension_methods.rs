@@ -0,0 +1,118 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpErrno, HttpMethod, ParserSettings};
+
+pub mod helper;
+
+fn settings() -> ParserSettings {
+    ParserSettings::new()
+        .method("LINK")
+        .method("QUERY")
+        .method("ACL")
+        .method("BIND")
+        .method("SOURCE")
+        .method("POSTX")
+        .method("GE")
+        .method("X-VENDOR_1")
+}
+
+fn parse(settings: ParserSettings, data: &str) -> HttpParser {
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    if hp.errno.is_none() {
+        assert_eq!(parsed, data.len());
+    }
+    hp
+}
+
+#[test]
+fn test_extension_methods() {
+    let methods = ["LINK", "QUERY", "ACL", "BIND", "SOURCE", "POSTX", "GE", "X-VENDOR_1"];
+    for method in methods.iter() {
+        let data = format!("{} /resource HTTP/1.1\r\n\r\n", method);
+        let hp = parse(settings(), &data);
+
+        assert!(hp.errno.is_none(), "{}", method);
+        assert!(hp.method == Option::Some(HttpMethod::Extension(method)), "{}", method);
+        assert_eq!(hp.method.unwrap().to_string(), *method);
+    }
+}
+
+#[test]
+fn test_extension_method_byte_by_byte() {
+    let data = "QUERY /search HTTP/1.1\r\n\
+                Content-Length: 3\r\n\
+                \r\n\
+                a=b";
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings());
+    let mut cb = helper::CallbackEmpty;
+
+    for b in data.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert!(hp.method == Option::Some(HttpMethod::Extension("QUERY")));
+}
+
+#[test]
+fn test_builtin_methods_are_kept() {
+    let hp = parse(settings(), "POST / HTTP/1.1\r\n\r\n");
+    assert!(hp.method == Option::Some(HttpMethod::Post));
+
+    let hp = parse(settings(), "GET / HTTP/1.1\r\n\r\n");
+    assert!(hp.method == Option::Some(HttpMethod::Get));
+
+    let hp = parse(settings(), "LOCK / HTTP/1.1\r\n\r\n");
+    assert!(hp.method == Option::Some(HttpMethod::Lock));
+}
+
+#[test]
+fn test_unregistered_methods() {
+    let invalid = ["LINK / HTTP/1.1\r\n\r\n", "link / HTTP/1.1\r\n\r\n"];
+    for data in invalid.iter() {
+        assert!(parse(ParserSettings::new(), data).errno == Option::Some(HttpErrno::InvalidMethod));
+    }
+
+    let invalid = ["LINKS / HTTP/1.1\r\n\r\n", "LIN / HTTP/1.1\r\n\r\n",
+                   "link / HTTP/1.1\r\n\r\n", "POS / HTTP/1.1\r\n\r\n"];
+    for data in invalid.iter() {
+        assert!(parse(settings(), data).errno == Option::Some(HttpErrno::InvalidMethod), "{}", data);
+    }
+}
+
+#[test]
+fn test_error_at_first_unknown_byte() {
+    let hp = parse(settings(), "LIMK / HTTP/1.1\r\n\r\n");
+
+    assert_eq!(hp.error().unwrap().offset, 2);
+}
+
+#[test]
+fn test_pipelined_extension_methods() {
+    let data = "LINK /a HTTP/1.1\r\n\r\n\
+                GET /b HTTP/1.1\r\n\r\n\
+                BIND /c HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings());
+    let mut cb = helper::CallbackEmpty;
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.method == Option::Some(HttpMethod::Extension("BIND")));
+}
+
+#[test]
+fn test_extension_method_in_both_mode() {
+    let data = "HELLO / HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::with_settings(HttpParserType::Both, ParserSettings::new().method("HELLO"));
+    let mut cb = helper::CallbackEmpty;
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.errno.is_none());
+    assert!(hp.method == Option::Some(HttpMethod::Extension("HELLO")));
+
+    let mut hp = HttpParser::with_settings(HttpParserType::Both, ParserSettings::new().method("HELLO"));
+    let data = "HEAD / HTTP/1.1\r\n\r\n";
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.method == Option::Some(HttpMethod::Head));
+}
@@ -5,7 +5,7 @@ use self::hap_http_parser::{HttpParser, HttpParserCallback, HttpParserType,
 
 pub mod helper;
 
-const HEADER_LINE : &'static str = "header-key: header-value\r\n";
+const HEADER_LINE : &str = "header-key: header-value\r\n";
 
 #[test]
 fn test_request_header() {
@@ -0,0 +1,73 @@
+extern crate hap_http_parser;
+
+use std::collections::HashSet;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpErrno, HttpMethod};
+
+pub mod helper;
+
+#[test]
+fn test_round_trip() {
+    for method in HttpMethod::iter() {
+        assert_eq!(method.as_str().parse::<HttpMethod>(), Ok(method));
+        assert_eq!(method.to_string(), method.as_str());
+    }
+    assert_eq!(HttpMethod::iter().count(), 27);
+}
+
+#[test]
+fn test_from_str() {
+    assert_eq!("M-SEARCH".parse::<HttpMethod>(), Ok(HttpMethod::MSearch));
+    assert_eq!("get".parse::<HttpMethod>(), Err(HttpErrno::InvalidMethod));
+    assert_eq!("LINK".parse::<HttpMethod>(), Err(HttpErrno::InvalidMethod));
+    assert_eq!("".parse::<HttpMethod>(), Err(HttpErrno::InvalidMethod));
+}
+
+#[test]
+fn test_map_key() {
+    let methods: HashSet<HttpMethod> = HttpMethod::iter()
+        .chain(Some(HttpMethod::Extension("LINK")))
+        .collect();
+
+    assert_eq!(methods.len(), 28);
+    assert!(methods.contains(&HttpMethod::Put));
+    assert!(methods.contains(&HttpMethod::Extension("LINK")));
+    assert_eq!(format!("{:?}", HttpMethod::Put), "Put");
+    assert_eq!(HttpMethod::Extension("LINK").to_string(), "LINK");
+}
+
+#[test]
+fn test_semantics() {
+    assert!(HttpMethod::Get.is_safe());
+    assert!(HttpMethod::Get.is_idempotent());
+    assert!(!HttpMethod::Get.allows_request_body());
+
+    assert!(!HttpMethod::Put.is_safe());
+    assert!(HttpMethod::Put.is_idempotent());
+    assert!(HttpMethod::Put.allows_request_body());
+
+    assert!(!HttpMethod::Post.is_idempotent());
+    assert!(HttpMethod::Post.allows_request_body());
+    assert!(!HttpMethod::Trace.allows_request_body());
+
+    assert!(!HttpMethod::Head.expects_response_body());
+    assert!(!HttpMethod::Connect.expects_response_body());
+    assert!(HttpMethod::Delete.expects_response_body());
+
+    assert!(!HttpMethod::Extension("QUERY").is_safe());
+    assert!(HttpMethod::Extension("QUERY").allows_request_body());
+
+    // Every safe method is idempotent
+    assert!(HttpMethod::iter().filter(|m| m.is_safe()).all(|m| m.is_idempotent()));
+}
+
+#[test]
+fn test_parse_put() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, b"PUT /resource HTTP/1.1\r\n\r\n");
+
+    assert!(hp.errno.is_none());
+    assert_eq!(hp.method, Option::Some(HttpMethod::Put));
+}
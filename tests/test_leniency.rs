@@ -0,0 +1,179 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpErrno, Leniency, ParserSettings};
+
+pub mod helper;
+
+#[test]
+fn test_bare_lf() {
+    let data = "GET / HTTP/1.1\n\
+                Host: example.com\n\
+                \n";
+    test_leniency(Leniency { bare_lf: true, ..Leniency::none() }, data, HttpErrno::Strict);
+}
+
+#[test]
+fn test_bare_cr() {
+    // A bare CR never ends a line, the byte after it isn't taken for the next line
+    let requests = [
+        ("GET / HTTP/1.1\rHost: a\r\n\r\n", 15),
+        ("GET / HTTP/1.1\r\nHost: a\rX-Y: b\r\n\r\n", 24),
+        ("GET / HTTP/1.1\r\nHost:\rX-Y: b\r\n\r\n", 22),
+        ("GET / HTTP/1.1\r\nHost: a\r\n\rX\r\n", 26),
+    ];
+
+    for &(data, offset) in requests.iter() {
+        for leniency in [Leniency::none(), Leniency::all()].iter() {
+            let settings = ParserSettings::new().leniency(*leniency);
+            let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+            let mut cb = helper::CallbackEmpty;
+
+            assert_eq!(hp.execute(&mut cb, data.as_bytes()), offset, "{:?}", data);
+            assert!(hp.errno == Option::Some(HttpErrno::LFExpected), "{:?}", data);
+        }
+    }
+}
+
+#[test]
+fn test_header_name_spaces() {
+    let data = "GET / HTTP/1.1\r\n\
+                Host Name: example.com\r\n\
+                \r\n";
+    test_leniency(Leniency { header_name_spaces: true, ..Leniency::none() }, data,
+                  HttpErrno::InvalidHeaderToken);
+}
+
+#[test]
+fn test_chunk_crlf() {
+    let chunks = [
+        // After the chunk size
+        "5\nhello\r\n0\r\n\r\n",
+        "5 \nhello\r\n0\r\n\r\n",
+        "5;a\nhello\r\n0\r\n\r\n",
+        "5;a=b\nhello\r\n0\r\n\r\n",
+        // After the chunk data
+        "3\r\nabc\n0\r\n\r\n",
+        // Both
+        "3\nabc\n0\n\r\n",
+    ];
+
+    for chunk in chunks.iter() {
+        let data = format!("POST / HTTP/1.1\r\n\
+                            Transfer-Encoding: chunked\r\n\
+                            \r\n\
+                            {}", chunk);
+        test_leniency(Leniency { chunk_crlf: true, ..Leniency::none() }, &data, HttpErrno::Strict);
+    }
+}
+
+#[test]
+fn test_chunk_crlf_keeps_data() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\nhello\n\
+                0\r\n\r\n";
+    let settings = ParserSettings::new().leniency(Leniency { chunk_crlf: true, ..Leniency::none() });
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackRegular{..Default::default()};
+    cb.messages.push(helper::Message{..Default::default()});
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert_eq!(cb.messages[0].body, "hello");
+    assert!(cb.messages[0].message_complete_cb_called);
+}
+
+#[test]
+fn test_chunk_crlf_bad_terminators() {
+    // A bare CR isn't a line end and a chunk longer than its size isn't truncated
+    let chunks = [
+        ("5\rhello\r\n0\r\n\r\n", 49, HttpErrno::LFExpected),
+        ("3\r\nabc\r0\r\n\r\n", 54, HttpErrno::LFExpected),
+        ("3\r\nabc__0\r\n\r\n", 53, HttpErrno::InvalidChunkSize),
+    ];
+
+    for &(chunk, offset, errno) in chunks.iter() {
+        let data = format!("POST / HTTP/1.1\r\n\
+                            Transfer-Encoding: chunked\r\n\
+                            \r\n\
+                            {}", chunk);
+        for leniency in [Leniency::none(), Leniency::all()].iter() {
+            let settings = ParserSettings::new().leniency(*leniency);
+            let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+            let mut cb = helper::CallbackEmpty;
+
+            assert_eq!(hp.execute(&mut cb, data.as_bytes()), offset, "{:?}", chunk);
+            assert!(hp.errno == Option::Some(errno), "{:?}", chunk);
+        }
+    }
+}
+
+#[test]
+fn test_keep_alive() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: close\r\n\
+                \r\n\
+                GET / HTTP/1.1\r\n\
+                \r\n";
+    test_leniency(Leniency { keep_alive: true, ..Leniency::none() }, data,
+                  HttpErrno::ClosedConnection);
+}
+
+#[test]
+fn test_protocol() {
+    let data = "GET / HXXP/1.1\r\n\
+                \r\n";
+    test_leniency(Leniency { protocol: true, ..Leniency::none() }, data, HttpErrno::Strict);
+}
+
+#[test]
+fn test_url_chars() {
+    let data = "GET /a\tb HTTP/1.1\r\n\
+                \r\n";
+    test_leniency(Leniency { url_chars: true, ..Leniency::none() }, data, HttpErrno::InvalidPath);
+}
+
+#[test]
+fn test_ambiguous_framing() {
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 3\r\n\
+                Content-Length: 3\r\n\
+                \r\n\
+                abc";
+    test_leniency(Leniency { ambiguous_framing: true, ..Leniency::none() }, data,
+                  HttpErrno::DuplicateContentLength);
+}
+
+#[test]
+fn test_request_target() {
+    let data = "NOTIFY * HTTP/1.1\r\n\
+                \r\n";
+    test_leniency(Leniency { request_target: true, ..Leniency::none() }, data, HttpErrno::Strict);
+}
+
+#[test]
+fn test_leniencies_are_independent() {
+    // Tolerating a bare LF doesn't tolerate a bad protocol literal
+    let data = "GET / HXXP/1.1\n\
+                \n";
+    let leniency = Leniency { bare_lf: true, ..Leniency::none() };
+    assert!(parse(leniency, data) == Option::Some(HttpErrno::Strict));
+    assert!(parse(Leniency::all(), data).is_none());
+}
+
+fn test_leniency(leniency: Leniency, data: &str, err_expected: HttpErrno) {
+    assert!(parse(Leniency::none(), data) == Option::Some(err_expected));
+    assert!(parse(leniency, data).is_none());
+}
+
+fn parse(leniency: Leniency, data: &str) -> Option<HttpErrno> {
+    let settings = ParserSettings::new().leniency(leniency);
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    if hp.errno.is_none() {
+        assert_eq!(parsed, data.len());
+    }
+    hp.errno
+}
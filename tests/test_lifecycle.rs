@@ -0,0 +1,137 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ParserSettings};
+
+pub mod helper;
+
+#[derive(Default)]
+struct CallbackCount {
+    messages: usize,
+    body: Vec<u8>,
+}
+
+impl HttpParserCallback for CallbackCount {
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body.extend_from_slice(data);
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.messages += 1;
+        Ok(ParseAction::None)
+    }
+}
+
+#[test]
+fn test_finish_body_until_eof() {
+    let data = "HTTP/1.1 200 OK\r\n\
+                \r\n\
+                hello";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = CallbackCount{..Default::default()};
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert_eq!(cb.messages, 0);
+
+    assert!(hp.finish(&mut cb).is_ok());
+    assert_eq!(cb.messages, 1);
+    assert_eq!(cb.body, b"hello");
+
+    // The message is only completed once
+    assert!(hp.finish(&mut cb).is_ok());
+    assert_eq!(cb.messages, 1);
+}
+
+#[test]
+fn test_finish_between_messages() {
+    let data = "GET / HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCount{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.finish(&mut cb).is_ok());
+    assert_eq!(cb.messages, 1);
+}
+
+#[test]
+fn test_finish_in_the_middle_of_a_message() {
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 10\r\n\
+                \r\n\
+                01234";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCount{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    let error = hp.finish(&mut cb).unwrap_err();
+    assert!(error.errno == HttpErrno::InvalidEofState);
+    assert_eq!(error.offset, data.len() as u64);
+    assert_eq!(error.state, "in body");
+    assert_eq!(cb.messages, 0);
+}
+
+#[test]
+fn test_finish_paused() {
+    let data = "GET / HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCount{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+    hp.pause(true);
+
+    assert!(hp.finish(&mut cb).unwrap_err().errno == HttpErrno::Paused);
+
+    hp.resume();
+    assert!(hp.finish(&mut cb).is_ok());
+}
+
+#[test]
+fn test_reset_after_error() {
+    let settings = ParserSettings::new().max_url_size(8);
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = CallbackCount{..Default::default()};
+
+    hp.execute(&mut cb, b"GET /0123456789 HTTP/1.1\r\n\r\n");
+    assert!(hp.errno == Option::Some(HttpErrno::UrlOverflow));
+
+    hp.reset(HttpParserType::Request);
+    assert!(hp.errno.is_none());
+    assert!(hp.error().is_none());
+
+    let data = "POST /short HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                \r\n\
+                hello";
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.messages, 1);
+    assert_eq!(cb.body, b"hello");
+
+    // The settings are kept
+    hp.execute(&mut cb, b"GET /0123456789 HTTP/1.1\r\n\r\n");
+    assert!(hp.errno == Option::Some(HttpErrno::UrlOverflow));
+    assert_eq!(hp.error().unwrap().offset, (data.len() + 12) as u64);
+}
+
+#[test]
+fn test_reset_in_the_middle_of_a_message() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCount{..Default::default()};
+
+    hp.execute(&mut cb, b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel");
+
+    hp.reset(HttpParserType::Response);
+    assert!(hp.finish(&mut cb).is_ok());
+
+    let data = "HTTP/1.1 200 OK\r\n\
+                Content-Length: 2\r\n\
+                \r\n\
+                ok";
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert_eq!(hp.status_code, Option::Some(200));
+    assert_eq!(cb.messages, 1);
+    assert_eq!(cb.body, b"helok");
+}
@@ -33,7 +33,7 @@ fn test_no_overflow_long_body(tp: HttpParserType, length: u64) {
     assert_eq!(parsed, headers.len());
 
     for _ in 0..length {
-        parsed = hp.execute(&mut cb, &[b'a']);
+        parsed = hp.execute(&mut cb, b"a");
         assert_eq!(parsed, 1);
     }
 
@@ -0,0 +1,161 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ParserSettings, ObsFold};
+
+pub mod helper;
+
+const FOLDED: &str = "GET / HTTP/1.1\r\n\
+                      X-Long: first\r\n \t second\r\n\
+                      Connection: keep-alive,\r\n\tclose\r\n\
+                      X-Empty: a\r\n \r\n\
+                      Host: example.com\r\n\
+                      \r\n";
+
+#[derive(Default)]
+struct CallbackValues {
+    fragments: Vec<String>,
+    values: Vec<String>,
+}
+
+impl HttpParserCallback for CallbackValues {
+    fn on_header_field(&mut self, _ : &mut HttpParser, _ : &[u8]) -> CallbackResult {
+        if !self.fragments.is_empty() {
+            let value = self.fragments.concat();
+            self.values.push(value);
+            self.fragments.clear();
+        }
+        Ok(ParseAction::None)
+    }
+
+    fn on_header_value(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.fragments.push(String::from_utf8_lossy(data).to_string());
+        Ok(ParseAction::None)
+    }
+
+    fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        self.on_header_field(parser, b"")
+    }
+}
+
+fn parse(obs_fold: ObsFold, data: &str) -> (HttpParser, CallbackValues) {
+    let mut hp = HttpParser::with_settings(HttpParserType::Request,
+                                           ParserSettings::new().obs_fold(obs_fold));
+    let mut cb = CallbackValues{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+    (hp, cb)
+}
+
+#[test]
+fn test_keep() {
+    let (hp, cb) = parse(ObsFold::Keep, FOLDED);
+
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.values, vec!["first \t second", "keep-alive,\tclose", "a ", "example.com"]);
+}
+
+#[test]
+fn test_reject() {
+    let (hp, cb) = parse(ObsFold::Reject, FOLDED);
+
+    assert!(hp.errno == Option::Some(HttpErrno::ObsFold));
+    assert_eq!(hp.error().unwrap().line, 3);
+    assert_eq!(hp.error().unwrap().column, 1);
+    assert!(cb.values.is_empty());
+}
+
+#[test]
+fn test_unfold() {
+    let (hp, cb) = parse(ObsFold::Unfold, FOLDED);
+
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.values, vec!["first second", "keep-alive, close", "a", "example.com"]);
+    assert!(hp.has_connection_keep_alive());
+    assert!(hp.has_connection_close());
+    assert_eq!(hp.connection_tokens(), vec!["keep-alive", "close"]);
+}
+
+#[test]
+fn test_unfold_byte_by_byte() {
+    let mut hp = HttpParser::with_settings(HttpParserType::Request,
+                                           ParserSettings::new().obs_fold(ObsFold::Unfold));
+    let mut cb = CallbackValues{..Default::default()};
+
+    for b in FOLDED.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert_eq!(cb.values, vec!["first second", "keep-alive, close", "a", "example.com"]);
+}
+
+#[test]
+fn test_unfold_single_space() {
+    let data = "GET / HTTP/1.1\r\n\
+                X-A: a \t\r\n \t b\r\n\
+                X-B: a  b \r\n\t\r\n  c \r\n\
+                \r\n";
+    let (hp, cb) = parse(ObsFold::Unfold, data);
+
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.values, vec!["a b", "a  b c"]);
+
+    let mut hp = HttpParser::with_settings(HttpParserType::Request,
+                                           ParserSettings::new().obs_fold(ObsFold::Unfold));
+    let mut cb = CallbackValues{..Default::default()};
+    for b in data.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+    assert_eq!(cb.values, vec!["a b", "a  b c"]);
+}
+
+#[test]
+fn test_unfold_trailer() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                0\r\n\
+                Vary: a,\r\n  b\r\n\
+                \r\n";
+    let (hp, cb) = parse(ObsFold::Unfold, data);
+
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.values, vec!["chunked"]);
+    assert_eq!(cb.fragments.concat(), "a, b");
+}
+
+#[test]
+fn test_unfold_with_pauses() {
+    struct CallbackPause(CallbackValues);
+
+    impl HttpParserCallback for CallbackPause {
+        fn on_header_field(&mut self, parser : &mut HttpParser, data : &[u8]) -> CallbackResult {
+            self.0.on_header_field(parser, data)
+        }
+
+        fn on_header_value(&mut self, parser : &mut HttpParser, data : &[u8]) -> CallbackResult {
+            self.0.on_header_value(parser, data)?;
+            Ok(ParseAction::Pause)
+        }
+
+        fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+            self.0.on_headers_complete(parser)
+        }
+    }
+
+    let data = FOLDED.as_bytes();
+    let mut hp = HttpParser::with_settings(HttpParserType::Request,
+                                           ParserSettings::new().obs_fold(ObsFold::Unfold));
+    let mut cb = CallbackPause(CallbackValues{..Default::default()});
+
+    let mut parsed = 0;
+    while parsed < data.len() {
+        parsed += hp.execute(&mut cb, &data[parsed..]);
+        if hp.is_paused() {
+            hp.resume();
+        }
+        assert!(hp.errno.is_none());
+    }
+
+    assert_eq!(cb.0.values, vec!["first second", "keep-alive, close", "a", "example.com"]);
+}
@@ -0,0 +1,185 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ParseError};
+
+pub mod helper;
+
+const INVALID_HEADER: &str = "GET / HTTP/1.1\r\n\
+                              Host: example.com\r\n\
+                              Acc(ept: */*\r\n\
+                              \r\n";
+
+#[test]
+fn test_error_position() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, INVALID_HEADER.as_bytes());
+
+    assert_eq!(parsed, 38);
+    assert_eq!(hp.error(), Option::Some(&ParseError {
+        errno: HttpErrno::InvalidHeaderToken,
+        offset: 38,
+        line: 3,
+        column: 4,
+        state: "in header field",
+        reason: Option::None,
+    }));
+}
+
+#[test]
+fn test_error_position_byte_by_byte() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    for b in INVALID_HEADER.as_bytes() {
+        if hp.execute(&mut cb, &[*b]) != 1 {
+            break;
+        }
+    }
+
+    let error = hp.error().unwrap();
+    assert_eq!(error.offset, 38);
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, 4);
+}
+
+#[test]
+fn test_error_position_in_pipelined_message() {
+    let data = format!("GET /first HTTP/1.1\r\n\r\n{}", INVALID_HEADER);
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    // The offset is counted from the start of the stream, the line from the start of the message
+    let error = hp.error().unwrap();
+    assert_eq!(error.offset, 23 + 38);
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, 4);
+}
+
+#[test]
+fn test_error_on_line_feed() {
+    let data = "GET / HTTP/1.1\r\n\
+                Host: example.com\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    let error = hp.error().unwrap();
+    assert!(error.errno == HttpErrno::Strict);
+    assert_eq!(error.offset, data.len() as u64 - 1);
+    assert_eq!(error.line, 2);
+    assert_eq!(error.column, 18);
+    assert_eq!(error.state, "in header value");
+}
+
+#[test]
+fn test_error_in_url() {
+    let data = "GET /a\x7fb HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert_eq!(hp.error(), Option::Some(&ParseError {
+        errno: HttpErrno::InvalidPath,
+        offset: 6,
+        line: 1,
+        column: 7,
+        state: "in URL path",
+        reason: Option::None,
+    }));
+}
+
+#[test]
+fn test_no_error() {
+    let data = "GET / HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.error().is_none());
+}
+
+#[test]
+fn test_no_error_when_paused() {
+    struct CallbackPauseUrl;
+
+    impl HttpParserCallback for CallbackPauseUrl {
+        fn on_url(&mut self, _ : &mut HttpParser, _ : &[u8]) -> CallbackResult {
+            Ok(ParseAction::Pause)
+        }
+    }
+
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackPauseUrl;
+
+    hp.execute(&mut cb, INVALID_HEADER.as_bytes());
+
+    assert!(hp.is_paused());
+    assert!(hp.error().is_none());
+}
+
+#[test]
+fn test_display() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, INVALID_HEADER.as_bytes());
+
+    assert_eq!(hp.error().unwrap().to_string(),
+               "invalid character in header at line 3, column 4 (in header field, offset 38)");
+}
+
+#[test]
+fn test_callback_error_reason() {
+    struct CallbackRejectAid;
+
+    impl HttpParserCallback for CallbackRejectAid {
+        fn on_header_value(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+            if data == b"7" {
+                return Err("unknown aid 7".to_string());
+            }
+            Ok(ParseAction::None)
+        }
+    }
+
+    let data = "POST /characteristics HTTP/1.1\r\n\
+                Aid: 7\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackRejectAid;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::CBHeaderValue));
+    assert_eq!(hp.callback_error(), Option::Some("unknown aid 7"));
+    assert_eq!(hp.error().unwrap().reason, Option::Some("unknown aid 7".to_string()));
+    assert_eq!(hp.error().unwrap().to_string(),
+               "the on_header_value callback failed at line 2, column 8 \
+                (at end of header, offset 39): unknown aid 7");
+}
+
+#[test]
+fn test_callback_error_reason_from_headers_complete() {
+    struct CallbackReject;
+
+    impl HttpParserCallback for CallbackReject {
+        fn on_headers_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+            Err("not authorized".to_string())
+        }
+    }
+
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackReject;
+
+    hp.execute(&mut cb, b"GET / HTTP/1.1\r\n\r\n");
+
+    assert!(hp.errno == Option::Some(HttpErrno::CBHeadersComplete));
+    assert_eq!(hp.callback_error(), Option::Some("not authorized"));
+}
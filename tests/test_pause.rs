@@ -0,0 +1,173 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+const PIPELINED: &str = "GET /first HTTP/1.1\r\n\
+                         \r\n\
+                         POST /second HTTP/1.1\r\n\
+                         Transfer-Encoding: chunked\r\n\
+                         \r\n\
+                         5;ext=1\r\nhello\r\n\
+                         0\r\n\
+                         Vary: *\r\n\
+                         \r\n";
+
+#[derive(Default)]
+struct CallbackEvents {
+    events: Vec<String>,
+    pause_on_every_event: bool,
+}
+
+impl CallbackEvents {
+    fn event(&mut self, name: &str) -> CallbackResult {
+        self.events.push(name.to_string());
+        if self.pause_on_every_event || name == "message" {
+            Ok(ParseAction::Pause)
+        } else {
+            Ok(ParseAction::None)
+        }
+    }
+}
+
+impl HttpParserCallback for CallbackEvents {
+    fn on_message_begin(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.event("begin")
+    }
+
+    fn on_url(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("url {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_header_field(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("field {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_header_value(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("value {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_headers_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.event("headers")
+    }
+
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("body {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_chunk_header(&mut self, _ : &mut HttpParser, size : u64) -> CallbackResult {
+        self.event(&format!("chunk {}", size))
+    }
+
+    fn on_chunk_extension_name(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("ext {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_chunk_extension_value(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.event(&format!("ext value {}", String::from_utf8_lossy(data)))
+    }
+
+    fn on_chunk_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.event("chunk complete")
+    }
+
+    fn on_trailers_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.event("trailers")
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.event("message")
+    }
+}
+
+#[test]
+fn test_pause_between_pipelined_requests() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackEvents{..Default::default()};
+    let data = PIPELINED.as_bytes();
+    let first_len = "GET /first HTTP/1.1\r\n\r\n".len();
+
+    let parsed = hp.execute(&mut cb, data);
+    assert_eq!(parsed, first_len);
+    assert!(hp.is_paused());
+    assert_eq!(cb.events.last().unwrap(), "message");
+    assert_eq!(cb.events.iter().filter(|e| *e == "begin").count(), 1);
+
+    // A paused parser doesn't consume anything
+    assert_eq!(hp.execute(&mut cb, &data[parsed..]), 0);
+
+    hp.resume();
+    assert!(!hp.is_paused());
+    assert_eq!(hp.execute(&mut cb, &data[parsed..]), data.len() - parsed);
+    assert!(hp.is_paused());
+    assert_eq!(cb.events.iter().filter(|e| *e == "message").count(), 2);
+}
+
+#[test]
+fn test_pause_on_every_event() {
+    let mut expected = CallbackEvents{..Default::default()};
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut data = PIPELINED.as_bytes();
+    while !data.is_empty() {
+        let parsed = hp.execute(&mut expected, data);
+        hp.resume();
+        data = &data[parsed..];
+    }
+
+    let mut cb = CallbackEvents { pause_on_every_event: true, ..Default::default() };
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut data = PIPELINED.as_bytes();
+    let mut pauses = 0;
+    while !data.is_empty() {
+        let events = cb.events.len();
+        let parsed = hp.execute(&mut cb, data);
+
+        // Exactly one event per execute
+        assert!(hp.is_paused());
+        assert_eq!(cb.events.len(), events + 1);
+        pauses += 1;
+
+        hp.resume();
+        data = &data[parsed..];
+    }
+
+    assert!(hp.errno.is_none());
+    assert_eq!(pauses, expected.events.len());
+    assert_eq!(cb.events, expected.events);
+}
+
+#[test]
+fn test_pause_from_headers_complete() {
+    struct CallbackPauseHeaders {
+        body: String,
+    }
+
+    impl HttpParserCallback for CallbackPauseHeaders {
+        fn on_headers_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+            Ok(ParseAction::Pause)
+        }
+
+        fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+            self.body.push_str(&String::from_utf8_lossy(data));
+            Ok(ParseAction::None)
+        }
+    }
+
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                \r\n\
+                hello";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackPauseHeaders { body: String::new() };
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    assert!(hp.errno == Option::Some(HttpErrno::Paused));
+    assert!(cb.body.is_empty());
+
+    hp.resume();
+    assert_eq!(hp.execute(&mut cb, &data.as_bytes()[parsed..]), data.len() - parsed);
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.body, "hello");
+}
@@ -0,0 +1,59 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno, CallbackResult,
+                      ParseAction, RequestTarget};
+
+pub mod helper;
+
+struct CallbackTarget {
+    target: Option<RequestTarget>,
+}
+
+impl HttpParserCallback for CallbackTarget {
+    fn on_url(&mut self, parser: &mut HttpParser, _ : &[u8]) -> CallbackResult {
+        self.target = parser.request_target();
+        Ok(ParseAction::None)
+    }
+}
+
+fn parse(request_line: &str) -> (Option<RequestTarget>, Option<HttpErrno>) {
+    let data = format!("{}\r\n\r\n", request_line);
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackTarget { target: None };
+
+    hp.execute(&mut cb, data.as_bytes());
+    (cb.target, hp.errno)
+}
+
+#[test]
+fn test_forms() {
+    assert_eq!(parse("GET /accessories HTTP/1.1"), (Some(RequestTarget::Origin), None));
+    assert_eq!(parse("GET http://example.com/ HTTP/1.1"), (Some(RequestTarget::Absolute), None));
+    assert_eq!(parse("CONNECT example.com:443 HTTP/1.1"), (Some(RequestTarget::Authority), None));
+    assert_eq!(parse("OPTIONS * HTTP/1.1"), (Some(RequestTarget::Asterisk), None));
+}
+
+#[test]
+fn test_asterisk_form_requires_options() {
+    assert_eq!(parse("GET * HTTP/1.1"), (None, Some(HttpErrno::Strict)));
+    assert_eq!(parse("OPTIONS *foo HTTP/1.1"), (None, Some(HttpErrno::Strict)));
+}
+
+#[test]
+fn test_authority_form_is_host_and_port() {
+    assert_eq!(parse("CONNECT example.com HTTP/1.1"), (None, Some(HttpErrno::Strict)));
+    assert_eq!(parse("CONNECT example.com:443/ HTTP/1.1"), (None, Some(HttpErrno::Strict)));
+    assert_eq!(parse("CONNECT user@example.com:443 HTTP/1.1"), (None, Some(HttpErrno::Strict)));
+}
+
+#[test]
+fn test_reset_between_messages() {
+    let mut hp = HttpParser::new(HttpParserType::Both);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, b"OPTIONS * HTTP/1.1\r\n\r\n");
+    assert_eq!(hp.request_target(), Some(RequestTarget::Asterisk));
+
+    hp.execute(&mut cb, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+    assert_eq!(hp.request_target(), None);
+}
@@ -2,7 +2,7 @@ extern crate hap_http_parser;
 
 use std::default::Default;
 
-use hap_http_parser::{HttpParser, HttpParserType, HttpVersion};
+use hap_http_parser::{HttpParser, HttpParserType, HttpVersion, Leniency, ParserSettings};
 
 pub mod helper;
 
@@ -183,6 +183,7 @@ fn test_responses() {
         helper::Message {
             name: "no carriage ret".to_string(),
             tp: HttpParserType::Response,
+            leniency: Leniency { bare_lf: true, ..Leniency::none() },
             raw: "HTTP/1.1 200 OK\n\
                 Content-Type: text/html; charset=utf-8\n\
                 Connection: close\n\
@@ -564,7 +565,7 @@ fn test_responses() {
         helper::Message {
             name: "field space".to_string(),
             tp: HttpParserType::Response,
-            strict: false,
+            leniency: Leniency::all(),
             raw: "HTTP/1.1 200 OK\r\n\
                 Server: Microsoft-IIS/6.0\r\n\
                 X-Powered-By: ASP.NET\r\n\
@@ -601,7 +602,7 @@ fn test_responses() {
         helper::Message {
             name: "amazon.com".to_string(),
             tp: HttpParserType::Response,
-            strict: false,
+            leniency: Leniency::all(),
             raw: "HTTP/1.1 301 MovedPermanently\r\n\
                 Date: Wed, 15 May 2013 17:06:33 GMT\r\n\
                 Server: Server\r\n\
@@ -667,8 +668,10 @@ fn test_responses() {
         helper::Message {
             name: "HAP Event".to_string(),
             tp: HttpParserType::Response,
+            leniency: Leniency { bare_lf: true, ..Leniency::none() },
             raw: "EVENT/1.0 200 OK\n\
                 Content-Type: application/hap+json\n\
+                Content-Length: 68\n\
                 \n\
                 {\n\
                     \"characteristics\" : [\n\
@@ -680,8 +683,8 @@ fn test_responses() {
                     ]\n\
                 }\
                 ".to_string(),
-            should_keep_alive: false,
-            message_complete_on_eof: true,
+            should_keep_alive: true,
+            message_complete_on_eof: false,
             http_version: HttpVersion { major: 1, minor: 0 },
             status_code: Some(200),
             response_status: {
@@ -693,6 +696,7 @@ fn test_responses() {
             },
             headers: vec![
                 [ "Content-Type".to_string(), "application/hap+json".to_string() ],
+                [ "Content-Length".to_string(), "68".to_string() ],
             ],
             body: "{\n\
                 \"characteristics\" : [\n\
@@ -763,6 +767,8 @@ fn test_responses() {
                 [ "Content-Type".to_string(), "text/plain".to_string() ],
             ],
             body_size: 31337*1024,
+            // HTTP/1.0 has no Transfer-Encoding
+            leniency: Leniency { ambiguous_framing: true, ..Leniency::none() },
             ..Default::default()
         };
         test_message_count_body(&large_chunked);
@@ -780,8 +786,7 @@ fn test_responses() {
 }
 
 fn test_message_count_body(msg: &helper::Message) {
-    let mut hp = HttpParser::new(msg.tp);
-    hp.strict = msg.strict;
+    let mut hp = HttpParser::with_settings(msg.tp, ParserSettings::new().leniency(msg.leniency));
 
     let mut cb = helper::CallbackCountBody{..Default::default()};
     cb.messages.push(helper::Message{..Default::default()});
@@ -0,0 +1,144 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction, ParserSettings};
+
+pub mod helper;
+
+#[test]
+fn test_default_settings() {
+    let data = "GET /test HTTP/1.1\r\n\
+                Host: example.com\r\n\
+                Accept: */*\r\n\
+                \r\n";
+    test_limit(ParserSettings::new(), data, Option::None);
+}
+
+#[test]
+fn test_max_header_size() {
+    let data = "GET / HTTP/1.1\r\n\
+                Host: example.com\r\n\
+                \r\n";
+    test_limit(ParserSettings::new().max_header_size(data.len()), data, Option::None);
+    test_limit(ParserSettings::new().max_header_size(20), data,
+               Option::Some(HttpErrno::HeaderOverflow));
+}
+
+#[test]
+fn test_max_start_line_size() {
+    let data = "GET /0123456789 HTTP/1.1\r\n\r\n";
+    test_limit(ParserSettings::new().max_start_line_size(26), data, Option::None);
+    test_limit(ParserSettings::new().max_start_line_size(25), data,
+               Option::Some(HttpErrno::StartLineOverflow));
+}
+
+#[test]
+fn test_max_url_size() {
+    let data = "GET /0123456789?a=b HTTP/1.1\r\n\r\n";
+    test_limit(ParserSettings::new().max_url_size(15), data, Option::None);
+    test_limit(ParserSettings::new().max_url_size(14), data,
+               Option::Some(HttpErrno::UrlOverflow));
+}
+
+#[test]
+fn test_max_headers() {
+    let data = "GET / HTTP/1.1\r\n\
+                A: 1\r\n\
+                B: 2\r\n\
+                C: 3\r\n\
+                \r\n";
+    test_limit(ParserSettings::new().max_headers(3), data, Option::None);
+    test_limit(ParserSettings::new().max_headers(2), data,
+               Option::Some(HttpErrno::HeaderCountOverflow));
+}
+
+#[test]
+fn test_max_header_value_size() {
+    let data = "GET / HTTP/1.1\r\n\
+                Short: abc\r\n\
+                Long: 0123456789\r\n\
+                \r\n";
+    test_limit(ParserSettings::new().max_header_value_size(10), data, Option::None);
+    test_limit(ParserSettings::new().max_header_value_size(9), data,
+               Option::Some(HttpErrno::HeaderValueOverflow));
+}
+
+#[test]
+fn test_limits_across_calls() {
+    let data = "GET /0123456789 HTTP/1.1\r\n\r\n";
+    let mut hp = HttpParser::with_settings(HttpParserType::Request,
+                                           ParserSettings::new().max_url_size(10));
+    let mut cb = helper::CallbackEmpty;
+
+    for b in data.as_bytes() {
+        if hp.execute(&mut cb, &[*b]) != 1 {
+            break;
+        }
+    }
+    assert!(hp.errno == Option::Some(HttpErrno::UrlOverflow));
+}
+
+#[test]
+fn test_max_body_size_content_length() {
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 10\r\n\
+                \r\n\
+                0123456789";
+    test_body_limit(HttpParserType::Request, 10, data, Option::None, 10);
+    test_body_limit(HttpParserType::Request, 9, data, Option::Some(HttpErrno::BodyTooLarge), 0);
+}
+
+#[test]
+fn test_max_body_size_chunked() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\r\n01234\r\n\
+                5\r\n56789\r\n\
+                0\r\n\r\n";
+    test_body_limit(HttpParserType::Request, 10, data, Option::None, 10);
+    test_body_limit(HttpParserType::Request, 9, data, Option::Some(HttpErrno::BodyTooLarge), 5);
+}
+
+#[test]
+fn test_max_body_size_eof() {
+    let data = "HTTP/1.1 200 OK\r\n\
+                \r\n\
+                0123456789";
+    test_body_limit(HttpParserType::Response, 10, data, Option::None, 10);
+    test_body_limit(HttpParserType::Response, 9, data, Option::Some(HttpErrno::BodyTooLarge), 0);
+}
+
+struct CallbackBodySize {
+    body_size: usize,
+}
+
+impl HttpParserCallback for CallbackBodySize {
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body_size += data.len();
+        Ok(ParseAction::None)
+    }
+}
+
+fn test_body_limit(tp: HttpParserType, max_body_size: u64, data: &str,
+                   err_expected: Option<HttpErrno>, body_size_expected: usize) {
+    let mut hp = HttpParser::with_settings(tp, ParserSettings::new().max_body_size(max_body_size));
+    let mut cb = CallbackBodySize { body_size: 0 };
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == err_expected);
+    assert_eq!(cb.body_size, body_size_expected);
+}
+
+fn test_limit(settings: ParserSettings, data: &str, err_expected: Option<HttpErrno>) {
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == err_expected);
+    if err_expected.is_none() {
+        assert_eq!(parsed, data.len());
+    }
+}
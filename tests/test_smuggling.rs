@@ -0,0 +1,180 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpErrno, Leniency, ParserSettings};
+
+pub mod helper;
+
+#[test]
+fn test_duplicate_content_length() {
+    let same = "POST / HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                Content-Length: 5\r\n\
+                \r\n\
+                hello";
+    let conflicting = "POST / HTTP/1.1\r\n\
+                       Content-Length: 5\r\n\
+                       Content-Length: 0\r\n\
+                       \r\n\
+                       hello";
+    assert!(parse(HttpParserType::Request, same) == Option::Some(HttpErrno::DuplicateContentLength));
+    assert!(parse(HttpParserType::Request, conflicting) ==
+            Option::Some(HttpErrno::DuplicateContentLength));
+
+    let response = "HTTP/1.1 200 OK\r\n\
+                    Content-Length: 5\r\n\
+                    Content-Length: 6\r\n\
+                    \r\n\
+                    hello";
+    assert!(parse(HttpParserType::Response, response) ==
+            Option::Some(HttpErrno::DuplicateContentLength));
+}
+
+#[test]
+fn test_content_length_and_transfer_encoding() {
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                0\r\n\r\n";
+    assert!(parse(HttpParserType::Request, data) ==
+            Option::Some(HttpErrno::UnexpectedContentLength));
+
+    // Any transfer coding, not just chunked
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: gzip\r\n\
+                Content-Length: 5\r\n\
+                \r\n\
+                hello";
+    assert!(parse(HttpParserType::Request, data) ==
+            Option::Some(HttpErrno::UnexpectedContentLength));
+}
+
+#[test]
+fn test_content_length_and_transfer_encoding_in_response() {
+    // Transfer-Encoding overrides Content-Length in a response
+    let data = "HTTP/1.1 200 OK\r\n\
+                Content-Length: 5\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\r\n";
+    assert!(parse(HttpParserType::Response, data).is_none());
+}
+
+#[test]
+fn test_transfer_encoding_in_http10() {
+    let request = "POST / HTTP/1.0\r\n\
+                   Transfer-Encoding: chunked\r\n\
+                   \r\n\
+                   0\r\n\r\n";
+    let response = "HTTP/1.0 200 OK\r\n\
+                    Transfer-Encoding: chunked\r\n\
+                    \r\n\
+                    0\r\n\r\n";
+    assert!(parse(HttpParserType::Request, request) ==
+            Option::Some(HttpErrno::InvalidTransferEncoding));
+    assert!(parse(HttpParserType::Response, response) ==
+            Option::Some(HttpErrno::InvalidTransferEncoding));
+}
+
+#[test]
+fn test_whitespace_in_content_length() {
+    let invalid = ["1 2", "1\t2", "12 3", "12 \r\n 3"];
+    for value in invalid.iter() {
+        let data = format!("POST / HTTP/1.1\r\n\
+                            Content-Length: {}\r\n\
+                            \r\n\
+                            hello", value);
+        assert!(parse(HttpParserType::Request, &data) ==
+                Option::Some(HttpErrno::InvalidContentLength), "{:?}", value);
+    }
+
+    // Whitespace around the digits is fine
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length:   5  \r\n\
+                \r\n\
+                hello";
+    assert!(parse(HttpParserType::Request, data).is_none());
+}
+
+#[test]
+fn test_empty_content_length() {
+    let invalid = ["POST / HTTP/1.1\r\nContent-Length:\r\n\r\nhello",
+                   "POST / HTTP/1.1\r\nContent-Length:  \r\n\r\nhello",
+                   "POST / HTTP/1.1\r\nContent-Length:\r\nHost: a\r\n\r\nhello"];
+    for data in invalid.iter() {
+        assert!(parse(HttpParserType::Request, data) ==
+                Option::Some(HttpErrno::InvalidContentLength), "{:?}", data);
+    }
+
+    // An empty value counts as a Content-Length header
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                Content-Length:\r\n\
+                \r\n\
+                hello";
+    assert!(parse(HttpParserType::Request, data) ==
+            Option::Some(HttpErrno::DuplicateContentLength));
+}
+
+#[test]
+fn test_chunked_applied_twice() {
+    let invalid = ["Transfer-Encoding: chunked, chunked\r\n",
+                   "Transfer-Encoding: chunked\r\nTransfer-Encoding: CHUNKED\r\n",
+                   "Transfer-Encoding: chunked, gzip, chunked\r\n"];
+    for headers in invalid.iter() {
+        let request = format!("POST / HTTP/1.1\r\n{}\r\n0\r\n\r\n", headers);
+        let response = format!("HTTP/1.1 200 OK\r\n{}\r\n0\r\n\r\n", headers);
+        assert!(parse(HttpParserType::Request, &request) ==
+                Option::Some(HttpErrno::InvalidTransferEncoding), "{:?}", headers);
+        assert!(parse(HttpParserType::Response, &response) ==
+                Option::Some(HttpErrno::InvalidTransferEncoding), "{:?}", headers);
+    }
+}
+
+#[test]
+fn test_error_offset() {
+    let data = "POST / HTTP/1.1\r\n\
+                Content-Length: 5\r\n\
+                Content-Length: 5\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackEmpty;
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    let error = hp.error().unwrap();
+    assert!(error.errno == HttpErrno::DuplicateContentLength);
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, 17);
+}
+
+#[test]
+fn test_ambiguous_framing_leniency() {
+    let data = "POST / HTTP/1.0\r\n\
+                Content-Length: 1 2\r\n\
+                Content-Length: 5\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\r\n";
+    let settings = ParserSettings::new()
+        .leniency(Leniency { ambiguous_framing: true, ..Leniency::none() });
+    let mut hp = HttpParser::with_settings(HttpParserType::Request, settings);
+    let mut cb = helper::CallbackEmpty;
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.errno.is_none());
+    assert!(hp.is_chunked());
+}
+
+fn parse(tp: HttpParserType, data: &str) -> Option<HttpErrno> {
+    let mut hp = HttpParser::new(tp);
+    let mut cb = helper::CallbackEmpty;
+
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    if hp.errno.is_none() {
+        assert_eq!(parsed, data.len());
+    }
+    hp.errno
+}
@@ -0,0 +1,136 @@
+extern crate hap_http_parser;
+
+use std::str;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+const CHUNKED_WITH_TRAILERS: &str = "POST /chunked HTTP/1.1\r\n\
+                                     Transfer-Encoding: chunked\r\n\
+                                     \r\n\
+                                     5\r\nhello\r\n\
+                                     0\r\n\
+                                     Vary: *\r\n\
+                                     Content-Type: text/plain\r\n\
+                                     \r\n";
+
+#[derive(Default)]
+struct CallbackTrailers {
+    headers: Vec<String>,
+    trailers: Vec<String>,
+    trailers_complete: bool,
+    message_complete: bool,
+}
+
+impl HttpParserCallback for CallbackTrailers {
+    fn on_header_field(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.headers.push(str::from_utf8(data).unwrap().to_string());
+        Ok(ParseAction::None)
+    }
+
+    fn on_trailer_field(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        let field = str::from_utf8(data).unwrap().to_string();
+        if field.eq_ignore_ascii_case("content-length") ||
+            field.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(format!("forbidden trailer field {}", field));
+        }
+        self.trailers.push(field);
+        Ok(ParseAction::None)
+    }
+
+    fn on_trailers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        assert!(parser.http_body_is_final());
+        assert!(!self.message_complete);
+        self.trailers_complete = true;
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        assert!(self.trailers_complete);
+        self.message_complete = true;
+        Ok(ParseAction::None)
+    }
+}
+
+#[test]
+fn test_trailer_callbacks() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackTrailers{..Default::default()};
+
+    let parsed = hp.execute(&mut cb, CHUNKED_WITH_TRAILERS.as_bytes());
+
+    assert_eq!(parsed, CHUNKED_WITH_TRAILERS.len());
+    assert_eq!(cb.headers, vec!["Transfer-Encoding".to_string()]);
+    assert_eq!(cb.trailers, vec!["Vary".to_string(), "Content-Type".to_string()]);
+    assert!(cb.message_complete);
+}
+
+#[test]
+fn test_trailer_callbacks_byte_by_byte() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackTrailers{..Default::default()};
+
+    for b in CHUNKED_WITH_TRAILERS.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert_eq!(cb.headers.concat(), "Transfer-Encoding");
+    assert_eq!(cb.trailers.concat(), "VaryContent-Type");
+    assert!(cb.message_complete);
+}
+
+#[test]
+fn test_forbidden_trailer() {
+    let data = "POST /chunked HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\
+                Content-Length: 5\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackTrailers{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno == Option::Some(HttpErrno::CBTrailerField));
+    assert!(!cb.message_complete);
+}
+
+#[test]
+fn test_trailers_default_to_headers() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = helper::CallbackRegular{..Default::default()};
+    cb.messages.push(helper::Message{..Default::default()});
+
+    hp.execute(&mut cb, CHUNKED_WITH_TRAILERS.as_bytes());
+
+    assert_eq!(cb.num_messages, 1);
+    assert_eq!(cb.messages[0].headers.len(), 3);
+    assert_eq!(cb.messages[0].headers[2][0], "Content-Type");
+    assert_eq!(cb.messages[0].headers[2][1], "text/plain");
+}
+
+#[test]
+fn test_trailers_leave_connection_and_framing() {
+    let data = "HTTP/1.1 200 OK\r\n\
+                Transfer-Encoding: chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\
+                Connection: close\r\n\
+                Transfer-Encoding: gzip\r\n\
+                Content-Length: 5\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = helper::CallbackEmpty;
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+
+    assert!(hp.errno.is_none());
+    assert!(hp.http_should_keep_alive());
+    assert!(!hp.has_connection_close());
+    assert_eq!(hp.transfer_codings(), vec!["chunked"]);
+}
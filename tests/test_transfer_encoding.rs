@@ -0,0 +1,136 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpErrno,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+#[derive(Default)]
+struct CallbackCodings {
+    codings: Vec<String>,
+    chunked: bool,
+    body: Vec<u8>,
+    message_complete: bool,
+}
+
+impl HttpParserCallback for CallbackCodings {
+    fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        self.codings = parser.transfer_codings().iter().map(|c| c.to_string()).collect();
+        self.chunked = parser.is_chunked();
+        Ok(ParseAction::None)
+    }
+
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body.extend_from_slice(data);
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.message_complete = true;
+        Ok(ParseAction::None)
+    }
+}
+
+#[test]
+fn test_coding_list() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: gzip, chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCodings{..Default::default()};
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.codings, vec!["gzip", "chunked"]);
+    assert!(cb.chunked);
+    assert_eq!(cb.body, b"hello");
+    assert!(cb.message_complete);
+}
+
+#[test]
+fn test_coding_list_across_headers() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: gzip ,, deflate\r\n\
+                Transfer-Encoding:\tChunked \r\n\
+                \r\n\
+                0\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCodings{..Default::default()};
+
+    hp.execute(&mut cb, data.as_bytes());
+
+    assert!(hp.errno.is_none());
+    assert_eq!(cb.codings, vec!["gzip", "deflate", "Chunked"]);
+    assert!(cb.chunked);
+}
+
+#[test]
+fn test_coding_list_byte_by_byte() {
+    let data = "POST / HTTP/1.1\r\n\
+                Transfer-Encoding: gzip,\r\n \
+                chunked\r\n\
+                \r\n\
+                5\r\nhello\r\n\
+                0\r\n\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCodings{..Default::default()};
+
+    for b in data.as_bytes() {
+        assert_eq!(hp.execute(&mut cb, &[*b]), 1);
+    }
+
+    assert_eq!(cb.codings, vec!["gzip", "chunked"]);
+    assert_eq!(cb.body, b"hello");
+}
+
+#[test]
+fn test_chunked_must_be_final_in_request() {
+    let invalid = ["chunked, gzip", "gzip", "chunkedx", "chunked;q=1"];
+    for codings in invalid.iter() {
+        let data = format!("POST / HTTP/1.1\r\n\
+                            Transfer-Encoding: {}\r\n\
+                            \r\n\
+                            0\r\n\r\n", codings);
+        let mut hp = HttpParser::new(HttpParserType::Request);
+        let mut cb = helper::CallbackEmpty;
+
+        hp.execute(&mut cb, data.as_bytes());
+
+        assert!(hp.errno == Option::Some(HttpErrno::InvalidTransferEncoding), "{:?}", codings);
+    }
+}
+
+#[test]
+fn test_response_without_final_chunked() {
+    // The body is read until the connection closes, even with a Content-Length
+    let data = "HTTP/1.1 200 OK\r\n\
+                Transfer-Encoding: chunked, gzip\r\n\
+                Content-Length: 2\r\n\
+                \r\n\
+                5\r\nhello\r\n";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let mut cb = CallbackCodings{..Default::default()};
+
+    assert_eq!(hp.execute(&mut cb, data.as_bytes()), data.len());
+    assert!(!cb.chunked);
+    assert!(!cb.message_complete);
+    assert!(!hp.http_should_keep_alive());
+    assert_eq!(hp.content_length(), Option::Some(2));
+
+    assert!(hp.finish(&mut cb).is_ok());
+    assert_eq!(cb.body, b"5\r\nhello\r\n");
+    assert!(cb.message_complete);
+}
+
+#[test]
+fn test_no_transfer_encoding() {
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let mut cb = CallbackCodings{..Default::default()};
+
+    hp.execute(&mut cb, b"GET / HTTP/1.1\r\n\r\n");
+
+    assert!(cb.codings.is_empty());
+    assert!(hp.transfer_codings().is_empty());
+}
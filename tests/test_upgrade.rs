@@ -0,0 +1,167 @@
+extern crate hap_http_parser;
+
+use hap_http_parser::{HttpParser, HttpParserType, HttpParserCallback, HttpMethod,
+                      CallbackResult, ParseAction};
+
+pub mod helper;
+
+#[derive(Default)]
+struct CallbackUpgrade {
+    upgrade_protocol: Option<String>,
+    body: String,
+    messages: usize,
+}
+
+impl HttpParserCallback for CallbackUpgrade {
+    fn on_headers_complete(&mut self, parser : &mut HttpParser) -> CallbackResult {
+        self.upgrade_protocol = parser.upgrade_protocol().map(|p| p.to_string());
+        Ok(ParseAction::None)
+    }
+
+    fn on_body(&mut self, _ : &mut HttpParser, data : &[u8]) -> CallbackResult {
+        self.body.push_str(&String::from_utf8_lossy(data));
+        Ok(ParseAction::None)
+    }
+
+    fn on_message_complete(&mut self, _ : &mut HttpParser) -> CallbackResult {
+        self.messages += 1;
+        Ok(ParseAction::None)
+    }
+}
+
+// Parses the message and returns the callback and the data that follows it
+fn parse<'a>(hp: &mut HttpParser, data: &'a str) -> (CallbackUpgrade, &'a str) {
+    let mut cb = CallbackUpgrade{..Default::default()};
+    let parsed = hp.execute(&mut cb, data.as_bytes());
+    assert!(hp.errno.is_none());
+    (cb, &data[parsed..])
+}
+
+#[test]
+fn test_request_upgrade() {
+    let data = "GET /stream HTTP/1.1\r\n\
+                Connection: keep-alive, Upgrade\r\n\
+                Upgrade: websocket\r\n\
+                \r\n\
+                tunnel";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let (cb, rest) = parse(&mut hp, data);
+
+    assert!(hp.upgrade);
+    assert!(hp.has_connection_upgrade());
+    assert!(hp.has_connection_keep_alive());
+    assert_eq!(cb.upgrade_protocol, Option::Some("websocket".to_string()));
+    assert_eq!(cb.messages, 1);
+    assert_eq!(rest, "tunnel");
+}
+
+#[test]
+fn test_request_upgrade_needs_connection_upgrade() {
+    let data = "POST /stream HTTP/1.1\r\n\
+                Upgrade: websocket\r\n\
+                Content-Length: 4\r\n\
+                \r\n\
+                body";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let (cb, rest) = parse(&mut hp, data);
+
+    assert!(!hp.upgrade);
+    assert!(hp.has_upgrade_header());
+    assert!(!hp.has_connection_upgrade());
+    assert_eq!(cb.body, "body");
+    assert_eq!(rest, "");
+}
+
+#[test]
+fn test_connection_tokens() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: TE, Upgrade-Insecure-Requests ,close\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    parse(&mut hp, data);
+
+    assert!(hp.has_connection_close());
+    assert!(!hp.has_connection_upgrade());
+    assert!(!hp.http_should_keep_alive());
+}
+
+#[test]
+fn test_response_101() {
+    let data = "HTTP/1.1 101 Switching Protocols\r\n\
+                Connection: upgrade\r\n\
+                Upgrade: HDS/1.0 \r\n\
+                \r\n\
+                tunnel";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let (cb, rest) = parse(&mut hp, data);
+
+    assert!(hp.upgrade);
+    assert_eq!(cb.upgrade_protocol, Option::Some("HDS/1.0".to_string()));
+    assert_eq!(rest, "tunnel");
+}
+
+#[test]
+fn test_response_101_without_upgrade_header() {
+    let data = "HTTP/1.1 101 Switching Protocols\r\n\
+                \r\n\
+                tunnel";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let (cb, rest) = parse(&mut hp, data);
+
+    assert!(hp.upgrade);
+    assert_eq!(cb.upgrade_protocol, Option::None);
+    assert_eq!(rest, "tunnel");
+}
+
+#[test]
+fn test_response_200_with_upgrade_header() {
+    let data = "HTTP/1.1 200 OK\r\n\
+                Connection: upgrade\r\n\
+                Upgrade: h2c\r\n\
+                Content-Length: 4\r\n\
+                \r\n\
+                body";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    let (cb, rest) = parse(&mut hp, data);
+
+    assert!(!hp.upgrade);
+    assert_eq!(cb.body, "body");
+    assert_eq!(rest, "");
+}
+
+#[test]
+fn test_response_to_connect() {
+    let data = "HTTP/1.1 200 Connection Established\r\n\
+                \r\n\
+                tunnel";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    hp.method = Option::Some(HttpMethod::Connect);
+    let (_, rest) = parse(&mut hp, data);
+
+    assert!(hp.upgrade);
+    assert_eq!(rest, "tunnel");
+
+    let data = "HTTP/1.1 407 Proxy Authentication Required\r\n\
+                Content-Length: 0\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Response);
+    hp.method = Option::Some(HttpMethod::Connect);
+    let (cb, _) = parse(&mut hp, data);
+
+    assert!(!hp.upgrade);
+    assert_eq!(cb.messages, 1);
+}
+
+#[test]
+fn test_multiple_upgrade_headers() {
+    let data = "GET / HTTP/1.1\r\n\
+                Connection: Upgrade\r\n\
+                Upgrade: h2c\r\n\
+                Upgrade: websocket\r\n\
+                \r\n";
+    let mut hp = HttpParser::new(HttpParserType::Request);
+    let (cb, _) = parse(&mut hp, data);
+
+    assert!(hp.upgrade);
+    assert_eq!(cb.upgrade_protocol, Option::Some("h2c, websocket".to_string()));
+}